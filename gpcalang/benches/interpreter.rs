@@ -0,0 +1,59 @@
+//! instructions per second of the interpreter on a 256x256 world.
+//!
+//! `fetch/decode` decodes every word as it runs, the way the interpreter did before
+//! entities kept their code decoded, `fetch/decoded` indexes the decoded program
+//! instead. To compare whole steps against another commit, save a baseline there
+//! with `cargo bench --features new2 --bench interpreter -- --save-baseline before`
+//! and run `cargo bench --features new2 --bench interpreter -- --baseline before`
+//! here.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use gpcalang::{EventResponse, Program, World};
+
+const SEED: u128 = 0xabdf1327932123ffabdf1327932123ff;
+const SIZE: u32 = 256;
+const ENTITIES: usize = 4096;
+const CODE_LEN: u32 = 40;
+
+/// without energy no entity dies and the flat cost model runs exactly one
+/// instruction per entity and step, so a step runs `ENTITIES` instructions.
+fn world() -> World {
+    let mut world = World::new(vec![], ENTITIES, SIZE, SIZE, false, 0.0, Some(SEED));
+    world.populate(ENTITIES, 0, CODE_LEN);
+    world
+}
+
+fn step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("step");
+    group.throughput(Throughput::Elements(ENTITIES as u64));
+    group.bench_function("sequential", |b| {
+        b.iter_batched_ref(world, |world| world.step(|_| {}, |_| {}), BatchSize::LargeInput)
+    });
+    group.finish();
+}
+
+fn fetch(c: &mut Criterion) {
+    let world = world();
+    let programs = world.get_entites().iter().map(|entity| entity.program().clone()).collect::<Vec<Program>>();
+    let instructions = programs.iter().map(Program::len).sum::<usize>();
+    let mut group = c.benchmark_group("fetch");
+    group.throughput(Throughput::Elements(instructions as u64));
+    group.bench_function("decode", |b| b.iter(|| {
+        for program in &programs {
+            for word in program.code() {
+                black_box(EventResponse::from_word(black_box(*word)));
+            }
+        }
+    }));
+    group.bench_function("decoded", |b| b.iter(|| {
+        for program in &programs {
+            for instruction in program.decoded() {
+                black_box(black_box(instruction));
+            }
+        }
+    }));
+    group.finish();
+}
+
+criterion_group!(benches, step, fetch);
+criterion_main!(benches);
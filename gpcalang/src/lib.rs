@@ -1,30 +1,34 @@
-#[cfg(not(feature="new2"))]
-#[cfg(not(feature="new"))]
-mod entity;
-#[cfg(not(feature="new2"))]
-#[cfg(not(feature="new"))]
-mod world;
-#[cfg(not(feature="new2"))]
-#[cfg(feature="new")]
-mod new;
-#[cfg(not(feature="new"))]
-mod new2;
-#[cfg(not(feature="new2"))]
-#[cfg(not(feature="new"))]
-pub use entity::*;
-#[cfg(not(feature="new2"))]
-#[cfg(not(feature="new"))]
-pub use world::*;
-#[cfg(not(feature="new2"))]
-#[cfg(feature="new")]
-pub use new::entity::*;
-#[cfg(not(feature="new2"))]
-#[cfg(feature="new")]
-pub use new::world::*;
-
-#[cfg(not(feature="new"))]
-#[cfg(feature="new2")]
-pub use new2::entity::*;
-#[cfg(not(feature="new"))]
-#[cfg(feature="new2")]
-pub use new2::world::*;
\ No newline at end of file
+#[cfg(not(feature="new2"))]
+#[cfg(not(feature="new"))]
+mod entity;
+#[cfg(not(feature="new2"))]
+#[cfg(not(feature="new"))]
+mod world;
+#[cfg(not(feature="new2"))]
+#[cfg(feature="new")]
+mod new;
+#[cfg(not(feature="new"))]
+#[cfg(feature="new2")]
+mod new2;
+#[cfg(not(feature="new2"))]
+#[cfg(not(feature="new"))]
+pub use entity::*;
+#[cfg(not(feature="new2"))]
+#[cfg(not(feature="new"))]
+pub use world::*;
+#[cfg(not(feature="new2"))]
+#[cfg(feature="new")]
+pub use new::entity::*;
+#[cfg(not(feature="new2"))]
+#[cfg(feature="new")]
+pub use new::world::*;
+
+#[cfg(not(feature="new"))]
+#[cfg(feature="new2")]
+pub use new2::entity::*;
+#[cfg(not(feature="new"))]
+#[cfg(feature="new2")]
+pub use new2::world::*;
+#[cfg(not(feature="new"))]
+#[cfg(feature="new2")]
+pub use new2::{entity, world};
\ No newline at end of file
@@ -1,35 +1,256 @@
-// use entity::GPCAEntity;
-// use rand::Rng;
-// use world::{boolmap::{place, BooleanMap}, World};
-
-use gpcalang::{GPCAEntity, World};
-use rand::Rng;
-
-
-// mod entity;
-// mod world;
-// fn main() {
-//     let mut map = World::new(vec![], 1, 2, 2);
-//     // map.set(0, 1, true);
-//     // map.set(0, 0, true);
-//     // map.set(1, 0, true);
-//     // map.set(2, 0, true);
-//     // map.set(3, 0, true);
-//     let count = map.surrounding_square_count(1, 1);
-//     println!("{}", count);
-//     map.step(|_|{}, |_|{});
-
-//     let mut entity = GPCAEntity::new(
-//         0, 0, 
-//         10, 
-//         0xf,
-//         0,0,
-//         vec![0b00000110_00000000_00000000_00000000]
-//         // (0..10).into_iter().map(|_|rand::thread_rng().gen_range(0..u32::MAX)).collect::<Vec<_>>()
-//     );
-//     for i in 0..20 {
-//         entity.execute_next(&mut map);
-//     }
-// }
-fn main() {
+use std::{fs::File, io::{BufReader, BufWriter, Write}, path::{Path, PathBuf}, process::ExitCode};
+
+use gpcalang::{world::{actions, config::{parse_seed, WorldConfig}}, Compact, EntityHandle, JsonLines, Metrics, MetricsCollector, ReplayLog};
+
+mod repl;
+
+const USAGE: &str = "usage: gpcalang [--config FILE] [--width N] [--height N] [--seed N] [--use-energy BOOL]
+                [--mutation-chance F] [--topology bounded|toroidal|cylinder]
+                [--crossover alternating|uniform|one_point|two_point|homologous]
+                [--schedule sequential|random|round_robin|energy] [--budget N]
+                [--step-mode sequential|parallel] [--threads N]
+                [--neighborhood moore|von_neumann[:RADIUS][:self]]
+                [--entities N] [--energy N] [--code-len N]
+                [--steps N] [--interval N] [--csv FILE] [--metrics FILE]
+                [--density-block N] [--record FILE] [--replay FILE] [--trace FILE]
+                [--debug HANDLE]
+
+the config file is a .toml or .json WorldConfig, options given on the command line
+override the file. --neighborhood replaces every configured neighborhood with a
+single one. --mutation-chance has no effect if the config sets a mutation
+pipeline. --step-mode parallel decides for every entity on --threads threads
+before applying the decisions in order, the result does not depend on --threads.
+every --interval steps a row of statistics is printed and written to --csv, the
+births, deaths and instruction counts in a row are since the previous one.
+--metrics writes the same rows as JSON Lines, with the genome lengths and the
+entities per --density-block by --density-block cells in full. --record writes a
+replay log of the run, --replay reruns the configured world against a log and
+reports the first step where they diverge. --trace writes every
+instruction entities run, as JSON Lines if FILE ends in .jsonl and in the compact
+binary format otherwise. --debug opens a debugger on the entity with that
+index:generation handle instead of running, the starting population has the
+handles 0:1, 1:1 and so on. The functions a config names are the standard actions:
+eat_top, eat_bottom, reproduce_top, reproduce_bottom_right, breed_top_left,
+breed_bottom and share_right.";
+
+fn parse<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String>
+    where T::Err: std::fmt::Display {
+    value.parse().map_err(|err| format!("invalid value '{value}' for --{key}: {err}"))
+}
+fn set(config: &mut WorldConfig, key: &str, value: &str) -> Result<(), String> {
+    match key {
+        "width" => config.width = parse(key, value)?,
+        "height" => config.height = parse(key, value)?,
+        "seed" => config.seed = Some(parse_seed(value).map_err(|err| format!("invalid value '{value}' for --{key}: {err}"))?),
+        "use-energy" => config.use_energy = parse(key, value)?,
+        "mutation-chance" => config.mutation_chance = parse(key, value)?,
+        "topology" => config.topology = parse(key, value)?,
+        "crossover" => config.crossover = parse(key, value)?,
+        "schedule" => config.schedule = parse(key, value)?,
+        "budget" => config.costs.budget = parse(key, value)?,
+        "step-mode" => config.step_mode = parse(key, value)?,
+        "threads" => config.threads = Some(parse(key, value)?),
+        "neighborhood" => config.neighborhoods = vec![parse(key, value)?],
+        "entities" => config.population.entities = parse(key, value)?,
+        "energy" => config.population.energy = parse(key, value)?,
+        "code-len" => config.population.code_len = parse(key, value)?,
+        "steps" => config.output.steps = parse(key, value)?,
+        "interval" => config.output.interval = parse(key, value)?,
+        "csv" => config.output.csv = Some(value.into()),
+        "metrics" => config.output.metrics = Some(value.into()),
+        "density-block" => config.output.density_block = parse(key, value)?,
+        "record" => config.output.record = Some(value.into()),
+        "trace" => config.output.trace = Some(value.into()),
+        _ => return Err(format!("unknown option '--{key}'")),
+    }
+    Ok(())
+}
+struct Options {
+    config: WorldConfig,
+    replay: Option<PathBuf>,
+    debug: Option<EntityHandle>,
+}
+fn options_from_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut config = WorldConfig::default();
+    let mut replay = None;
+    let mut debug = None;
+    let mut options = vec![];
+    while let Some(arg) = args.next() {
+        let key = arg.strip_prefix("--").ok_or_else(|| format!("unexpected argument '{arg}'"))?.to_string();
+        let value = args.next().ok_or_else(|| format!("missing value for --{key}"))?;
+        match key.as_str() {
+            "config" => config = WorldConfig::load(&value).map_err(|err| err.to_string())?,
+            "replay" => replay = Some(PathBuf::from(value)),
+            "debug" => debug = Some(parse(&key, &value)?),
+            _ => options.push((key, value)),
+        }
+    }
+    // the command line takes precedence over the config file regardless of order
+    for (key, value) in options {
+        set(&mut config, &key, &value)?;
+    }
+    config.validate().map_err(|err| err.to_string())?;
+    Ok(Options { config, replay, debug })
+}
+
+fn replay(config: WorldConfig, path: &Path) -> Result<(), String> {
+    let file = File::open(path).map_err(|err| format!("could not open {}: {err}", path.display()))?;
+    let log = ReplayLog::load(BufReader::new(file)).map_err(|err| err.to_string())?;
+    let mut world = config.build(actions::standard).map_err(|err| err.to_string())?;
+    log.replay(&mut world).map_err(|divergence| divergence.to_string())?;
+    println!("replayed {} steps without diverging", log.steps());
+    Ok(())
+}
+
+fn run(config: WorldConfig) -> Result<(), String> {
+    let mut world = config.build(actions::standard).map_err(|err| err.to_string())?;
+    if config.output.record.is_some() {
+        world.start_recording();
+    }
+    if let Some(path) = &config.output.trace {
+        let file = BufWriter::new(File::create(path).map_err(|err| format!("could not create {}: {err}", path.display()))?);
+        if path.extension().is_some_and(|ext| ext == "jsonl") {
+            world.start_tracing(JsonLines::new(file));
+        } else {
+            world.start_tracing(Compact::new(file));
+        }
+    }
+    let mut csv = match &config.output.csv {
+        Some(path) => Some(File::create(path).map_err(|err| format!("could not create {}: {err}", path.display()))?),
+        None => None,
+    };
+    let mut jsonl = match &config.output.metrics {
+        Some(path) => Some(BufWriter::new(File::create(path).map_err(|err| format!("could not create {}: {err}", path.display()))?)),
+        None => None,
+    };
+    let mut emit = |line: &str, metrics: Option<&Metrics>| -> Result<(), String> {
+        println!("{line}");
+        if let Some(csv) = csv.as_mut() {
+            writeln!(csv, "{line}").map_err(|err| format!("could not write csv: {err}"))?;
+        }
+        if let (Some(jsonl), Some(metrics)) = (jsonl.as_mut(), metrics) {
+            writeln!(jsonl, "{}", metrics.json()).map_err(|err| format!("could not write metrics: {err}"))?;
+        }
+        Ok(())
+    };
+    let mut collector = MetricsCollector::new(&world, config.output.density_block);
+    emit(Metrics::CSV_HEADER, None)?;
+    let mut step = 0;
+    while step < config.output.steps {
+        if step % config.output.interval == 0 {
+            let metrics = collector.sample(&world);
+            emit(&metrics.csv_row(), Some(&metrics))?;
+        }
+        if world.get_entites().is_empty() {
+            break;
+        }
+        world.step(|_| {}, |_| {});
+        step += 1;
+    }
+    if step == config.output.steps {
+        let metrics = collector.sample(&world);
+        emit(&metrics.csv_row(), Some(&metrics))?;
+    }
+    if let Some(Err(err)) = jsonl.map(|mut jsonl| jsonl.flush()) {
+        return Err(format!("could not write metrics: {err}"));
+    }
+    if let Some(Err(err)) = world.stop_tracing() {
+        return Err(format!("could not write trace: {err}"));
+    }
+    if let (Some(path), Some(log)) = (&config.output.record, world.stop_recording()) {
+        let file = File::create(path).map_err(|err| format!("could not create {}: {err}", path.display()))?;
+        log.save(BufWriter::new(file)).map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    if std::env::args().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    let options = match options_from_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let result = match (&options.replay, options.debug) {
+        (Some(path), _) => replay(options.config, path),
+        (None, Some(entity)) => repl::run(options.config, entity),
+        (None, None) => run(options.config),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gpcalang::{world::config::OutputConfig, Topology};
+
+    use super::*;
+
+    fn args(line: &str) -> impl Iterator<Item = String> + '_ {
+        line.split_whitespace().map(str::to_string)
+    }
+    /// a path in the temporary directory only this test process uses.
+    fn temp(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("gpcalang-{}-{name}", std::process::id()))
+    }
+    /// a small world that runs for `steps` steps.
+    fn tiny(steps: usize) -> WorldConfig {
+        let mut config = WorldConfig { width: 8, height: 6, ..WorldConfig::default() };
+        config.population.entities = 4;
+        config.output.steps = steps;
+        config.output.interval = 2;
+        config
+    }
+
+    #[test]
+    fn options_override_the_config_file() {
+        let path = temp("override.toml");
+        std::fs::write(&path, tiny(5).to_toml()).unwrap();
+        let line = format!("--width 16 --config {} --topology toroidal --debug 3:1", path.display());
+        let options = options_from_args(args(&line)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((options.config.width, options.config.height), (16, 6));
+        assert_eq!(options.config.topology, Topology::Toroidal);
+        assert_eq!(options.config.output.steps, 5);
+        assert_eq!(options.debug, Some(EntityHandle { index: 3, generation: 1 }));
+        assert_eq!(options.replay, None);
+        let options = options_from_args(args("--replay run.replay --seed 0x10")).unwrap();
+        assert_eq!((options.replay, options.config.seed), (Some(PathBuf::from("run.replay")), Some(16)));
+    }
+    #[test]
+    fn invalid_arguments_are_reported() {
+        let error = |line| options_from_args(args(line)).err().unwrap();
+        assert_eq!(error("--width"), "missing value for --width");
+        assert_eq!(error("width 8"), "unexpected argument 'width'");
+        assert_eq!(error("--colour red"), "unknown option '--colour'");
+        assert!(error("--width eight").starts_with("invalid value 'eight' for --width"));
+        assert!(error("--debug 3").starts_with("invalid value '3' for --debug"));
+        assert_eq!(error("--width 0"), "invalid size: 0x256 has no cells");
+        assert!(error("--config missing.yaml").contains("missing.yaml"));
+    }
+    #[test]
+    fn runs_write_a_row_every_interval() {
+        let csv = temp("rows.csv");
+        let config = WorldConfig { output: OutputConfig { csv: Some(csv.clone()), ..tiny(5).output }, ..tiny(5) };
+        run(config).unwrap();
+        let written = std::fs::read_to_string(&csv).unwrap();
+        std::fs::remove_file(&csv).unwrap();
+        let lines = written.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], Metrics::CSV_HEADER);
+        let columns = |line: &str| line.split(',').map(str::to_string).collect::<Vec<_>>();
+        let steps = lines[1..].iter().map(|line| columns(line)[0].clone()).collect::<Vec<_>>();
+        assert_eq!(steps, ["0", "2", "4", "5"]);
+        assert!(lines[1..].iter().all(|line| columns(line).len() == columns(Metrics::CSV_HEADER).len()));
+        assert_eq!(columns(lines[1])[1], "4");
+    }
 }
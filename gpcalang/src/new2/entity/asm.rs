@@ -0,0 +1,570 @@
+//! Text assembler for GPCA programs.
+//!
+//! Every line holds at most one instruction, optionally preceded by a label:
+//!
+//! ```text
+//! ; comments start with a semicolon
+//! start:
+//!     if surround >= 3 : move r0
+//!     if r0.b1 < r1.b2 : add r0.b1, r1.b2
+//!     jl r0, start
+//!     move 4
+//! ```
+//!
+//! * registers are `r0` and `r1` for the 64 bit registers and `r0.b0`..`r1.b3` for
+//!   the byte registers.
+//! * constants are `u8` literals written in decimal, `0x` hex or `0b` binary.
+//! * conditions are `if <reg> <cmp> <reg> :` or `if surround <cmp> <reg/const> :`
+//!   where `surround` is the surrounding square count in the first neighborhood of
+//!   the world and `surround.1`..`surround.7` count in the others. `layer` and
+//!   `layer.1`..`layer.3` compare the same way against the value of a world layer at
+//!   the current cell. `signal` compares against the signal at the current cell and
+//!   `signal.<direction>` against the neighboring cell, e.g. `signal.top_left`, where
+//!   constants have to be below 32. Instructions without a condition are
+//!   unconditional.
+//! * responses are `move`, `call` (`r0` or `r1` only), `harvest <layer>`,
+//!   `signal <reg>`, `nop`, the jumps `jmp`, `je`, `jne`, `jg`, `jl`,
+//!   `jge`, `jle` and the binary operations `add`, `sub`, `mul`, `div`, `xor`, `and`,
+//!   `or`, `mov`, `xchg` as well as their moving forms `madd`, `msub`, `mmul`, `mdiv`,
+//!   `mxor`, `mand`, `mor`.
+//! * conditional jumps take the register on the left of the comparison first, so
+//!   `jl r1, loop` jumps when `r1 < r0`. The lesser jump opcodes compare like their
+//!   lesser or equal forms, so `jl` is encoded as the greater jump of the other
+//!   register. Jump targets are labels or signed offsets relative to the next
+//!   instruction.
+use std::{collections::HashMap, fmt::Display};
+
+use super::{Direction, bytecode::{BinaryOp, EncodeError, Event, Jump, RegConst, Register, Response, LAYER_SELECTORS, NEIGHBORHOOD_SELECTORS}, EventResponse};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AsmErrorKind {
+    UnexpectedCharacter(char),
+    UnexpectedToken(String),
+    UnexpectedEnd,
+    UnknownMnemonic(String),
+    InvalidOperand(String),
+    ConstantOutOfRange(i64),
+    DuplicateLabel(String),
+    UndefinedLabel(String),
+    /// the distance to the label does not fit in the `i8` offset of a jump.
+    JumpOutOfRange { label: String, offset: i64 },
+    /// the instruction is well formed but no word decodes into it, e.g. mixing
+    /// 64 bit and byte registers.
+    Unencodable(EncodeError),
+}
+impl Display for AsmErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedCharacter(c) => write!(f, "unexpected character '{c}'"),
+            Self::UnexpectedToken(token) => write!(f, "unexpected '{token}'"),
+            Self::UnexpectedEnd => write!(f, "unexpected end of line"),
+            Self::UnknownMnemonic(mnemonic) => write!(f, "unknown mnemonic '{mnemonic}'"),
+            Self::InvalidOperand(operand) => write!(f, "invalid operand '{operand}'"),
+            Self::ConstantOutOfRange(constant) => write!(f, "constant {constant} does not fit in a byte"),
+            Self::DuplicateLabel(label) => write!(f, "label '{label}' is already defined"),
+            Self::UndefinedLabel(label) => write!(f, "label '{label}' is not defined"),
+            Self::JumpOutOfRange { label, offset } => write!(f, "jump to '{label}' needs offset {offset} which does not fit in an i8"),
+            Self::Unencodable(err) => err.fmt(f),
+        }
+    }
+}
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AsmError {
+    /// 1 based line of the error.
+    pub line: usize,
+    /// 1 based column of the error.
+    pub column: usize,
+    pub kind: AsmErrorKind,
+}
+impl Display for AsmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.kind)
+    }
+}
+impl std::error::Error for AsmError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum TokenKind {
+    Ident(String),
+    Number(i64),
+    Colon,
+    Comma,
+    Cmp(Comparison),
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Comparison {
+    Eq,
+    Neq,
+    Greater,
+    Lesser,
+    GreaterEq,
+    LesserEq,
+}
+impl Comparison {
+    /// the comparison with its operands swapped, `a < b` is `b > a`.
+    fn flip(self) -> Self {
+        match self {
+            Self::Eq => Self::Eq,
+            Self::Neq => Self::Neq,
+            Self::Greater => Self::Lesser,
+            Self::Lesser => Self::Greater,
+            Self::GreaterEq => Self::LesserEq,
+            Self::LesserEq => Self::GreaterEq,
+        }
+    }
+}
+#[derive(Clone, Debug)]
+struct Token {
+    kind: TokenKind,
+    column: usize,
+}
+impl Token {
+    fn text(&self) -> String {
+        match &self.kind {
+            TokenKind::Ident(ident) => ident.clone(),
+            TokenKind::Number(number) => number.to_string(),
+            TokenKind::Colon => ":".to_string(),
+            TokenKind::Comma => ",".to_string(),
+            TokenKind::Cmp(cmp) => match cmp {
+                Comparison::Eq => "==",
+                Comparison::Neq => "!=",
+                Comparison::Greater => ">",
+                Comparison::Lesser => "<",
+                Comparison::GreaterEq => ">=",
+                Comparison::LesserEq => "<=",
+            }.to_string(),
+        }
+    }
+}
+fn parse_number(text: &str) -> Option<i64> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(text) => (true, text),
+        None => (false, text),
+    };
+    let value = if let Some(hex) = text.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()?
+    } else if let Some(bin) = text.strip_prefix("0b") {
+        i64::from_str_radix(bin, 2).ok()?
+    } else {
+        text.parse::<i64>().ok()?
+    };
+    Some(if negative { -value } else { value })
+}
+fn tokenize(line: &str, line_number: usize) -> Result<Vec<Token>, AsmError> {
+    let chars = line.char_indices().collect::<Vec<_>>();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let (byte, c) = chars[i];
+        // columns are counted in characters so they line up with what an editor shows
+        let column = i + 1;
+        let error = |kind| AsmError { line: line_number, column, kind };
+        if c == ';' {
+            break;
+        } else if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let two = chars.get(i+1).map(|(_, c)| *c);
+        let (kind, len) = match (c, two) {
+            (':', _) => (TokenKind::Colon, 1),
+            (',', _) => (TokenKind::Comma, 1),
+            ('=', Some('=')) => (TokenKind::Cmp(Comparison::Eq), 2),
+            ('!', Some('=')) => (TokenKind::Cmp(Comparison::Neq), 2),
+            ('>', Some('=')) => (TokenKind::Cmp(Comparison::GreaterEq), 2),
+            ('<', Some('=')) => (TokenKind::Cmp(Comparison::LesserEq), 2),
+            ('>', _) => (TokenKind::Cmp(Comparison::Greater), 1),
+            ('<', _) => (TokenKind::Cmp(Comparison::Lesser), 1),
+            _ if c.is_ascii_alphanumeric() || c == '_' || c == '-' => {
+                let len = chars[i+1..].iter()
+                    .take_while(|(_, c)| c.is_ascii_alphanumeric() || *c == '_' || *c == '.')
+                    .count() + 1;
+                let end = chars.get(i+len).map(|(byte, _)| *byte).unwrap_or(line.len());
+                let text = &line[byte..end];
+                if c.is_ascii_digit() || c == '-' {
+                    let number = parse_number(text).ok_or_else(|| error(AsmErrorKind::InvalidOperand(text.to_string())))?;
+                    (TokenKind::Number(number), len)
+                } else {
+                    (TokenKind::Ident(text.to_ascii_lowercase()), len)
+                }
+            }
+            _ => return Err(error(AsmErrorKind::UnexpectedCharacter(c))),
+        };
+        tokens.push(Token { kind, column });
+        i += len;
+    }
+    Ok(tokens)
+}
+
+/// jump whose target is resolved once every label is known.
+#[derive(Clone, Debug)]
+enum JumpTarget {
+    Offset(i8),
+    Label(String),
+}
+/// a jump waiting for its offset.
+type JumpKind = fn(i8) -> Jump;
+#[derive(Clone, Debug)]
+enum PendingResponse {
+    Resolved(Response),
+    Jump(JumpKind, JumpTarget),
+}
+#[derive(Clone, Debug)]
+struct Instruction {
+    line: usize,
+    column: usize,
+    event: Event,
+    response: PendingResponse,
+}
+
+struct LineParser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    line: usize,
+    end_column: usize,
+}
+impl<'a> LineParser<'a> {
+    fn error_at(&self, column: usize, kind: AsmErrorKind) -> AsmError {
+        AsmError { line: self.line, column, kind }
+    }
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.pos)
+    }
+    fn next(&mut self) -> Result<&'a Token, AsmError> {
+        let token = self.tokens.get(self.pos).ok_or_else(|| self.error_at(self.end_column, AsmErrorKind::UnexpectedEnd))?;
+        self.pos += 1;
+        Ok(token)
+    }
+    fn expect(&mut self, kind: TokenKind) -> Result<(), AsmError> {
+        let token = self.next()?;
+        if token.kind == kind {
+            Ok(())
+        } else {
+            Err(self.error_at(token.column, AsmErrorKind::UnexpectedToken(token.text())))
+        }
+    }
+    fn finish(&self) -> Result<(), AsmError> {
+        match self.peek() {
+            Some(token) => Err(self.error_at(token.column, AsmErrorKind::UnexpectedToken(token.text()))),
+            None => Ok(()),
+        }
+    }
+    fn register(&mut self) -> Result<(Register, usize), AsmError> {
+        let token = self.next()?;
+        let TokenKind::Ident(ident) = &token.kind else {
+            return Err(self.error_at(token.column, AsmErrorKind::InvalidOperand(token.text())));
+        };
+        let Ok(register) = ident.parse::<Register>() else {
+            return Err(self.error_at(token.column, AsmErrorKind::InvalidOperand(token.text())));
+        };
+        Ok((register, token.column))
+    }
+    fn constant(&mut self) -> Result<u8, AsmError> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Number(number) => u8::try_from(number).map_err(|_| self.error_at(token.column, AsmErrorKind::ConstantOutOfRange(number))),
+            _ => Err(self.error_at(token.column, AsmErrorKind::InvalidOperand(token.text()))),
+        }
+    }
+    fn reg_const(&mut self) -> Result<RegConst, AsmError> {
+        match self.peek().map(|token| &token.kind) {
+            Some(TokenKind::Number(_)) => Ok(RegConst::Constant(self.constant()?)),
+            _ => Ok(RegConst::Register(self.register()?.0)),
+        }
+    }
+    fn comparison(&mut self) -> Result<Comparison, AsmError> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Cmp(cmp) => Ok(cmp),
+            _ => Err(self.error_at(token.column, AsmErrorKind::UnexpectedToken(token.text()))),
+        }
+    }
+    /// the selector of `name` or `name.n` where `n` is below `selectors`, `None` if the
+    /// next token is not `name`.
+    fn selector(&mut self, name: &str, selectors: u8) -> Result<Option<u8>, AsmError> {
+        let Some(token) = self.peek() else {
+            return Ok(None);
+        };
+        let TokenKind::Ident(ident) = &token.kind else {
+            return Ok(None);
+        };
+        let selected = match ident.strip_prefix(name) {
+            Some("") => 0,
+            Some(selector) => match selector.strip_prefix('.').and_then(|selector| selector.parse::<u8>().ok()) {
+                Some(selected) if selected < selectors => selected,
+                _ => return Err(self.error_at(token.column, AsmErrorKind::InvalidOperand(token.text()))),
+            },
+            None => return Ok(None),
+        };
+        self.pos += 1;
+        Ok(Some(selected))
+    }
+    /// `Some(None)` for `signal`, `Some(Some(direction))` for `signal.<direction>` and
+    /// `None` if the next token is not a signal.
+    fn signal(&mut self) -> Result<Option<Option<Direction>>, AsmError> {
+        let Some(token) = self.peek() else {
+            return Ok(None);
+        };
+        let TokenKind::Ident(ident) = &token.kind else {
+            return Ok(None);
+        };
+        let direction = match ident.strip_prefix("signal") {
+            Some("") => None,
+            Some(direction) => match Direction::ALL.iter().find(|dir| direction.strip_prefix('.') == Some(dir.name())) {
+                Some(direction) => Some(*direction),
+                None => return Err(self.error_at(token.column, AsmErrorKind::InvalidOperand(token.text()))),
+            },
+            None => return Ok(None),
+        };
+        self.pos += 1;
+        Ok(Some(direction))
+    }
+    fn event(&mut self) -> Result<Event, AsmError> {
+        if let Some(direction) = self.signal()? {
+            // compares like the surrounding square events below
+            let cmp = self.comparison()?.flip();
+            let lhs = self.reg_const()?;
+            return Ok(match cmp {
+                Comparison::Eq => Event::SignalEqual(lhs, direction),
+                Comparison::Neq => Event::SignalNotEqual(lhs, direction),
+                Comparison::Greater => Event::SignalGreater(lhs, direction),
+                Comparison::Lesser => Event::SignalLesser(lhs, direction),
+                Comparison::GreaterEq => Event::SignalGreaterEqual(lhs, direction),
+                Comparison::LesserEq => Event::SignalLesserEqual(lhs, direction),
+            });
+        }
+        if let Some(layer) = self.selector("layer", LAYER_SELECTORS)? {
+            // compares like the surrounding square events below
+            let cmp = self.comparison()?.flip();
+            let lhs = self.reg_const()?;
+            return Ok(match cmp {
+                Comparison::Eq => Event::LayerEqual(lhs, layer),
+                Comparison::Neq => Event::LayerNotEqual(lhs, layer),
+                Comparison::Greater => Event::LayerGreater(lhs, layer),
+                Comparison::Lesser => Event::LayerLesser(lhs, layer),
+                Comparison::GreaterEq => Event::LayerGreaterEqual(lhs, layer),
+                Comparison::LesserEq => Event::LayerLesserEqual(lhs, layer),
+            });
+        }
+        if let Some(neighborhood) = self.selector("surround", NEIGHBORHOOD_SELECTORS)? {
+            // `SurroundingSquaresGreater(n)` evaluates `n > count`, so `count > n` is the
+            // flipped comparison.
+            let cmp = self.comparison()?.flip();
+            let lhs = self.reg_const()?;
+            Ok(match cmp {
+                Comparison::Eq => Event::SurroundingSquaresEqual(lhs, neighborhood),
+                Comparison::Neq => Event::SurroundingSquaresNotEqual(lhs, neighborhood),
+                Comparison::Greater => Event::SurroundingSquaresGreater(lhs, neighborhood),
+                Comparison::Lesser => Event::SurroundingSquaresLesser(lhs, neighborhood),
+                Comparison::GreaterEq => Event::SurroundingSquaresGreaterEqual(lhs, neighborhood),
+                Comparison::LesserEq => Event::SurroundingSquaresLesserEqual(lhs, neighborhood),
+            })
+        } else {
+            let (lhs, _) = self.register()?;
+            let cmp = self.comparison()?;
+            let (rhs, _) = self.register()?;
+            Ok(match cmp {
+                Comparison::Eq => Event::Equal(lhs, rhs),
+                Comparison::Neq => Event::NotEqual(lhs, rhs),
+                Comparison::Greater => Event::Greater(lhs, rhs),
+                Comparison::Lesser => Event::Lesser(lhs, rhs),
+                Comparison::GreaterEq => Event::GreaterEqual(lhs, rhs),
+                Comparison::LesserEq => Event::LesserEqual(lhs, rhs),
+            })
+        }
+    }
+    fn jump_target(&mut self) -> Result<JumpTarget, AsmError> {
+        let token = self.next()?;
+        match &token.kind {
+            TokenKind::Ident(label) => Ok(JumpTarget::Label(label.clone())),
+            TokenKind::Number(number) => i8::try_from(*number)
+                .map(JumpTarget::Offset)
+                .map_err(|_| self.error_at(token.column, AsmErrorKind::ConstantOutOfRange(*number))),
+            _ => Err(self.error_at(token.column, AsmErrorKind::InvalidOperand(token.text()))),
+        }
+    }
+    fn response(&mut self) -> Result<PendingResponse, AsmError> {
+        let token = self.next()?;
+        let TokenKind::Ident(mnemonic) = &token.kind else {
+            return Err(self.error_at(token.column, AsmErrorKind::UnexpectedToken(token.text())));
+        };
+        let binary: Option<fn(Register, RegConst) -> BinaryOp> = match mnemonic.as_str() {
+            "add" => Some(BinaryOp::Add),
+            "sub" => Some(BinaryOp::Sub),
+            "mul" => Some(BinaryOp::Mul),
+            "div" => Some(BinaryOp::Div),
+            "xor" => Some(BinaryOp::Xor),
+            "and" => Some(BinaryOp::And),
+            "or" => Some(BinaryOp::Or),
+            "mov" => Some(BinaryOp::Mov),
+            "madd" => Some(BinaryOp::MoveAdd),
+            "msub" => Some(BinaryOp::MoveSub),
+            "mmul" => Some(BinaryOp::MoveMul),
+            "mdiv" => Some(BinaryOp::MoveDiv),
+            "mxor" => Some(BinaryOp::MoveXor),
+            "mand" => Some(BinaryOp::MoveAnd),
+            "mor" => Some(BinaryOp::MoveOr),
+            _ => None,
+        };
+        if let Some(binary) = binary {
+            let (lhs, _) = self.register()?;
+            self.expect(TokenKind::Comma)?;
+            let rhs = self.reg_const()?;
+            return Ok(PendingResponse::Resolved(Response::BinaryOp(binary(lhs, rhs))));
+        }
+        let conditional: Option<[JumpKind; 2]> = match mnemonic.as_str() {
+            "je" => Some([Jump::Reg0Eq, Jump::Reg1Eq]),
+            "jne" => Some([Jump::Reg0Neq, Jump::Reg1Neq]),
+            "jg" => Some([Jump::Reg0Greater, Jump::Reg1Greater]),
+            // `Jump::Reg0Lesser` jumps when `r0 <= r1`, `r1 > r0` is the strict form
+            "jl" => Some([Jump::Reg1Greater, Jump::Reg0Greater]),
+            "jge" => Some([Jump::Reg0GreaterEq, Jump::Reg1GreaterEq]),
+            "jle" => Some([Jump::Reg0LesserEq, Jump::Reg1LesserEq]),
+            _ => None,
+        };
+        if let Some([reg0, reg1]) = conditional {
+            let (register, column) = self.register()?;
+            let jump = match register {
+                Register::LongRegister0 => reg0,
+                Register::LongRegister1 => reg1,
+                _ => return Err(self.error_at(column, AsmErrorKind::InvalidOperand(format!("{register:?}")))),
+            };
+            self.expect(TokenKind::Comma)?;
+            return Ok(PendingResponse::Jump(jump, self.jump_target()?));
+        }
+        Ok(match mnemonic.as_str() {
+            "move" => PendingResponse::Resolved(Response::Move(self.reg_const()?)),
+            "call" => PendingResponse::Resolved(Response::Call(self.reg_const()?)),
+            "harvest" => PendingResponse::Resolved(Response::Harvest(self.constant()?)),
+            "signal" => PendingResponse::Resolved(Response::Signal(self.register()?.0)),
+            "nop" => PendingResponse::Resolved(Response::Nop),
+            "xchg" => {
+                let (lhs, _) = self.register()?;
+                self.expect(TokenKind::Comma)?;
+                let (rhs, _) = self.register()?;
+                PendingResponse::Resolved(Response::BinaryOp(BinaryOp::Xchg(lhs, rhs)))
+            }
+            "jmp" => PendingResponse::Jump(Jump::Unconditional, self.jump_target()?),
+            _ => return Err(self.error_at(token.column, AsmErrorKind::UnknownMnemonic(mnemonic.clone()))),
+        })
+    }
+}
+
+/// assembles `source` into the words decoded by [`super::GPCAEntity::parse`].
+pub fn assemble(source: &str) -> Result<Vec<u32>, AsmError> {
+    let mut labels = HashMap::new();
+    let mut instructions = vec![];
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let tokens = tokenize(line, line_number)?;
+        let mut parser = LineParser { tokens: &tokens, pos: 0, line: line_number, end_column: line.chars().count() + 1 };
+        if let [Token { kind: TokenKind::Ident(label), column }, Token { kind: TokenKind::Colon, .. }, ..] = tokens.as_slice() {
+            if label != "if" {
+                if labels.insert(label.clone(), instructions.len()).is_some() {
+                    return Err(parser.error_at(*column, AsmErrorKind::DuplicateLabel(label.clone())));
+                }
+                parser.pos = 2;
+            }
+        }
+        let Some(first) = parser.peek() else {
+            continue;
+        };
+        let column = first.column;
+        let event = if first.kind == TokenKind::Ident("if".to_string()) {
+            parser.pos += 1;
+            let event = parser.event()?;
+            parser.expect(TokenKind::Colon)?;
+            event
+        } else {
+            Event::Unconditional
+        };
+        let response = parser.response()?;
+        parser.finish()?;
+        instructions.push(Instruction { line: line_number, column, event, response });
+    }
+    instructions.iter().enumerate().map(|(idx, instruction)| {
+        let error = |kind| AsmError { line: instruction.line, column: instruction.column, kind };
+        let response = match &instruction.response {
+            PendingResponse::Resolved(response) => *response,
+            PendingResponse::Jump(jump, JumpTarget::Offset(offset)) => Response::Jmp(jump(*offset)),
+            PendingResponse::Jump(jump, JumpTarget::Label(label)) => {
+                let target = *labels.get(label).ok_or_else(|| error(AsmErrorKind::UndefinedLabel(label.clone())))?;
+                // the instruction pointer has already moved past the jump when the offset is applied
+                let offset = target as i64 - (idx as i64 + 1);
+                let offset = i8::try_from(offset).map_err(|_| error(AsmErrorKind::JumpOutOfRange { label: label.clone(), offset }))?;
+                Response::Jmp(jump(offset))
+            }
+        };
+        EventResponse { event: instruction.event, response }.to_word().map_err(|err| error(AsmErrorKind::Unencodable(err)))
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new2::entity::GPCAEntityInternal;
+
+    fn response(word: u32) -> Response {
+        EventResponse::from_word(word).response
+    }
+    fn error(source: &str) -> AsmError {
+        assemble(source).unwrap_err()
+    }
+
+    #[test]
+    fn labels_resolve_to_offsets_from_the_next_instruction() {
+        let code = assemble("start:\n    nop\n    jmp start\n    jl r1, end\n    nop\nend: nop\n    jge r0, -3").unwrap();
+        assert_eq!(code.len(), 6);
+        assert_eq!(response(code[1]), Response::Jmp(Jump::Unconditional(-2)));
+        assert_eq!(response(code[2]), Response::Jmp(Jump::Reg0Greater(1)));
+        assert_eq!(response(code[5]), Response::Jmp(Jump::Reg0GreaterEq(-3)));
+    }
+    #[test]
+    fn label_offsets_have_to_fit_in_an_i8() {
+        let source = |nops: usize| format!("start: nop\n{}jmp start", "nop\n".repeat(nops));
+        assert_eq!(response(*assemble(&source(126)).unwrap().last().unwrap()), Response::Jmp(Jump::Unconditional(-128)));
+        let err = error(&source(127));
+        assert_eq!((err.line, err.column), (129, 1));
+        assert_eq!(err.kind, AsmErrorKind::JumpOutOfRange { label: "start".to_string(), offset: -129 });
+        let err = error(&format!("jmp end\n{}end: nop", "nop\n".repeat(128)));
+        assert_eq!(err.kind, AsmErrorKind::JumpOutOfRange { label: "end".to_string(), offset: 128 });
+        assert_eq!(error("jmp 128").kind, AsmErrorKind::ConstantOutOfRange(128));
+    }
+    #[test]
+    fn comparison_jumps_run_as_written() {
+        for (mnemonic, compare) in [("jl", u64::lt as fn(&u64, &u64) -> bool), ("jg", u64::gt), ("jle", u64::le), ("jge", u64::ge), ("je", u64::eq), ("jne", u64::ne)] {
+            for (lhs, rhs) in [(2, 3), (3, 3), (4, 3)] {
+                for (register, registers) in [("r0", [lhs, rhs]), ("r1", [rhs, lhs])] {
+                    let Response::Jmp(jump) = response(assemble(&format!("{mnemonic} {register}, 5")).unwrap()[0]) else {
+                        panic!("{mnemonic} is not a jump");
+                    };
+                    let mut entity = GPCAEntityInternal::new(0, 0, registers[0], registers[1], 0);
+                    entity.handle_response(Response::Jmp(jump), 100);
+                    assert_eq!(entity.rip() == 5, compare(&lhs, &rhs), "{mnemonic} {register} with {lhs} and {rhs}");
+                }
+            }
+        }
+    }
+    #[test]
+    fn labels_are_unique_and_defined() {
+        let err = error("a: nop\n  a: nop");
+        assert_eq!((err.line, err.column, err.kind), (2, 3, AsmErrorKind::DuplicateLabel("a".to_string())));
+        let err = error("nop\n\n    je r0, nowhere");
+        assert_eq!((err.line, err.column, err.kind), (3, 5, AsmErrorKind::UndefinedLabel("nowhere".to_string())));
+    }
+    #[test]
+    fn errors_report_line_and_column() {
+        let err = error("nop\n  bogus r0");
+        assert_eq!((err.line, err.column, err.kind), (2, 3, AsmErrorKind::UnknownMnemonic("bogus".to_string())));
+        let err = error("; comment\nmove r0 $");
+        assert_eq!((err.line, err.column, err.kind), (2, 9, AsmErrorKind::UnexpectedCharacter('$')));
+        let err = error("add r0,");
+        assert_eq!((err.line, err.column, err.kind), (1, 8, AsmErrorKind::UnexpectedEnd));
+        let err = error("nop\nmove 256");
+        assert_eq!((err.line, err.column, err.kind), (2, 6, AsmErrorKind::ConstantOutOfRange(256)));
+        let err = error("nop\n  add r0, r1.b0");
+        assert_eq!((err.line, err.column), (2, 3));
+        assert_eq!(err.kind, AsmErrorKind::Unencodable(EncodeError::RegisterPair(Register::LongRegister0, Register::ByteRegister1_0)));
+        assert_eq!(err.to_string(), "2:3: registers r0 and r1.b0 can not be encoded as a pair");
+    }
+}
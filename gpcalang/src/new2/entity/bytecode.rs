@@ -1,239 +1,993 @@
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub enum Register {
-    LongRegister0,
-    LongRegister1,
-    ByteRegister0_0,
-    ByteRegister0_1,
-    ByteRegister0_2,
-    ByteRegister0_3,
-    ByteRegister1_0,
-    ByteRegister1_1,
-    ByteRegister1_2,
-    ByteRegister1_3,
-}
-impl From<u8> for Register {
-    fn from(value: u8) -> Self {
-        match value {
-            0b0 => Self::ByteRegister0_0,
-            0b1 => Self::ByteRegister0_1,
-            0b10 => Self::ByteRegister0_2,
-            0b11 => Self::ByteRegister0_3,
-            0b100 => Self::ByteRegister1_0,
-            0b101 => Self::ByteRegister1_1,
-            0b110 => Self::ByteRegister1_2,
-            0b111 => Self::ByteRegister1_3,
-            _ => panic!()
-        }
-    }
-}
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub enum RegConst {
-    Register(Register),
-    Constant(u8),
-}
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub enum Jump {
-    Unconditional(i8),
-    Reg0Eq(i8),
-    Reg0Neq(i8),
-    Reg0Greater(i8),
-    Reg0Lesser(i8),
-    Reg0GreaterEq(i8),
-    Reg0LesserEq(i8),
-    Reg1Eq(i8),
-    Reg1Neq(i8),
-    Reg1Greater(i8),
-    Reg1Lesser(i8),
-    Reg1GreaterEq(i8),
-    Reg1LesserEq(i8),
-}
-// Dst <- Src
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub enum BinaryOp {
-    Add(Register, RegConst),
-    Sub(Register, RegConst),
-    Mul(Register, RegConst),
-    Div(Register, RegConst),
-    Xor(Register, RegConst),
-    And(Register, RegConst),
-    Or(Register, RegConst),
-    Mov(Register, RegConst),
-    Xchg(Register, Register),
-    MoveAdd(Register, RegConst),
-    MoveSub(Register, RegConst),
-    MoveMul(Register, RegConst),
-    MoveDiv(Register, RegConst),
-    MoveXor(Register, RegConst),
-    MoveAnd(Register, RegConst),
-    MoveOr(Register, RegConst),
-}
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub enum Response {
-    Move(RegConst),
-    Jmp(Jump),
-    BinaryOp(BinaryOp),
-    Call(RegConst),
-    Nop
-}
-fn regbyte_lhs_rhs_ext(ext: u8) -> (Register, Register) {
-    if (ext&0b10000000) != 0 { // Is 64 bits
-        if (ext&0b01000000) != 0 {
-            (Register::LongRegister1, Register::LongRegister0)                
-        } else {
-            (Register::LongRegister0, Register::LongRegister1)                
-        }
-    } else { // Is 8 bits
-        let lhs = (ext>>3)&0x7;
-        let rhs = (ext)&0x7;
-        (Register::from(lhs), Register::from(rhs))
-    }
-}
-impl Response {
-    pub fn is_move_step(&self) -> bool {
-        if let Response::Move(_) = self {
-            true
-        } else {
-            if let Response::BinaryOp(BinaryOp::MoveAdd(_, _)) | 
-                   Response::BinaryOp(BinaryOp::MoveSub(_, _)) |
-                   Response::BinaryOp(BinaryOp::MoveMul(_, _)) |
-                   Response::BinaryOp(BinaryOp::MoveDiv(_, _)) |
-                   Response::BinaryOp(BinaryOp::MoveAnd(_, _)) |
-                   Response::BinaryOp(BinaryOp::MoveXor(_, _)) |
-                   Response::BinaryOp(BinaryOp::MoveOr(_, _)) = self {
-                true
-            } else {
-                false
-            }
-        }
-    }
-    fn top_layer(op: u8, ext: u8) -> Self {
-        match op {
-            0b0 =>          Self::Move(RegConst::Register(Register::LongRegister0)),
-            0b1 =>          Self::Move(RegConst::Register(Register::LongRegister1)),
-            0b10 =>         Self::Call(RegConst::Register(Register::LongRegister0)),
-            0b11 =>         Self::Call(RegConst::Register(Register::LongRegister1)),
-            0b01000 =>      Self::Jmp(Jump::Reg0Eq(ext as i8)),
-            0b01001 =>      Self::Jmp(Jump::Reg0Neq(ext as i8)),
-            0b01010 =>      Self::Jmp(Jump::Reg0Greater(ext as i8)),
-            0b01011 =>      Self::Jmp(Jump::Reg0Lesser(ext as i8)),
-            0b01100 =>      Self::Jmp(Jump::Reg0GreaterEq(ext as i8)),
-            0b01101 =>      Self::Jmp(Jump::Reg0LesserEq(ext as i8)),
-            0b1110 =>       Self::Jmp(Jump::Unconditional(ext as i8)),
-            0b1111 =>       Self::Move(RegConst::Constant(ext)),
-            0b100000 =>     Self::Jmp(Jump::Reg1Eq(ext as i8)),
-            0b100001 =>     Self::Jmp(Jump::Reg1Neq(ext as i8)),
-            0b100010 =>     Self::Jmp(Jump::Reg1Greater(ext as i8)),
-            0b100011 =>     Self::Jmp(Jump::Reg1Lesser(ext as i8)),
-            0b100100 =>     Self::Jmp(Jump::Reg1GreaterEq(ext as i8)),
-            0b100101 =>     Self::Jmp(Jump::Reg1LesserEq(ext as i8)),
-            0b1000000 =>    Self::Move(RegConst::Register(Register::LongRegister0)),
-            0b1000001 =>    Self::Move(RegConst::Register(Register::LongRegister1)),
-            0b1100000 =>    Self::Move(RegConst::Register(Register::LongRegister0)),
-            0b1100001 =>    Self::Move(RegConst::Register(Register::LongRegister1)),
-            0b10000000 =>   Self::Move(RegConst::Register(Register::LongRegister0)),
-            0b10000001 =>   Self::Move(RegConst::Register(Register::LongRegister1)),
-            0b11000000 =>   Self::Move(RegConst::Register(Register::LongRegister0)),
-            0b11000001 =>   Self::Move(RegConst::Register(Register::LongRegister1)),
-            // 0b11100000 =>   Self::Move(RegConst::Register(Register::LongRegister0)),
-            // 0b11100001 =>   Self::Move(RegConst::Register(Register::LongRegister1)),
-            _ => {
-                if (op&0b10000) != 0 {
-                    let (lhs, rhs) = regbyte_lhs_rhs_ext(ext);
-                    match op {
-                        0b10000 => Self::BinaryOp(BinaryOp::Add(lhs, RegConst::Register(rhs))),
-                        0b10001 => Self::BinaryOp(BinaryOp::Sub(lhs, RegConst::Register(rhs))),
-                        0b10010 => Self::BinaryOp(BinaryOp::Mul(lhs, RegConst::Register(rhs))),
-                        0b10011 => Self::BinaryOp(BinaryOp::Div(lhs, RegConst::Register(rhs))),
-                        0b10100 => Self::BinaryOp(BinaryOp::Xor(lhs, RegConst::Register(rhs))),
-                        0b10101 => Self::BinaryOp(BinaryOp::And(lhs, RegConst::Register(rhs))),
-                        0b10110 => Self::BinaryOp(BinaryOp::Or(lhs, RegConst::Register(rhs))),
-                        0b10111 => Self::BinaryOp(BinaryOp::Mov(lhs, RegConst::Register(rhs))),
-                        0b11111 => Self::BinaryOp(BinaryOp::Xchg(lhs, rhs)),
-                        0b110000 => Self::BinaryOp(BinaryOp::MoveAdd(lhs, RegConst::Register(rhs))),
-                        0b110001 => Self::BinaryOp(BinaryOp::MoveSub(lhs, RegConst::Register(rhs))),
-                        0b110010 => Self::BinaryOp(BinaryOp::MoveMul(lhs, RegConst::Register(rhs))),
-                        0b110011 => Self::BinaryOp(BinaryOp::MoveDiv(lhs, RegConst::Register(rhs))),
-                        0b110100 => Self::BinaryOp(BinaryOp::MoveXor(lhs, RegConst::Register(rhs))),
-                        0b110101 => Self::BinaryOp(BinaryOp::MoveAnd(lhs, RegConst::Register(rhs))),
-                        0b110110 => Self::BinaryOp(BinaryOp::MoveOr(lhs, RegConst::Register(rhs))),
-                        0b111000 => Self::Move(RegConst::Constant(0)),
-                        0b111001 => Self::Move(RegConst::Constant(4)),
-                        0b111010 => Self::Move(RegConst::Constant(2)),
-                        0b111011 => Self::Move(RegConst::Constant(6)),
-                        _ => Response::Nop,
-                    }
-                } else if (op&0b10000000) != 0 {
-                    match op {
-                        0b10000000 => Self::Call(RegConst::Register(Register::LongRegister0)),
-                        0b10000001 => Self::Call(RegConst::Register(Register::LongRegister1)),
-                        0b11111111 => Self::Call(RegConst::Constant(ext)),
-                        _ => Response::Nop,
-                    }
-                } else {
-                    Response::Nop
-                }
-            }
-        }
-    }
-}
-impl From<u16> for Response {
-    fn from(value: u16) -> Self {
-        let op = ((value >> 8)&0xff) as u8;
-        let ext = (value&0xff) as u8;
-        Response::top_layer(op, ext)
-    }
-}
-/// Events generate boolean values to see whether a respone
-/// should be executed or not. Every single Response needs an
-/// Event.
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub enum Event {
-    Unconditional,
-    Equal(Register, Register),
-    NotEqual(Register, Register),
-    Greater(Register, Register),
-    Lesser(Register, Register),
-    GreaterEqual(Register, Register),
-    LesserEqual(Register, Register),
-    SurroundingSquaresEqual(RegConst),
-    SurroundingSquaresNotEqual(RegConst),
-    SurroundingSquaresGreater(RegConst),
-    SurroundingSquaresLesser(RegConst),
-    SurroundingSquaresGreaterEqual(RegConst),
-    SurroundingSquaresLesserEqual(RegConst),
-}
-
-impl Event {
-    fn top_layer(op: u8, ext: u8) -> Self {
-        let (lhs, rhs) = regbyte_lhs_rhs_ext(ext);
-        match op {
-            0b0 => Self::Equal(lhs, rhs),
-            0b1 => Self::NotEqual(lhs, rhs),
-            0b10 => Self::Greater(lhs, rhs),
-            0b11 => Self::Lesser(lhs, rhs),
-            0b100 => Self::GreaterEqual(lhs, rhs),
-            0b101 => Self::LesserEqual(lhs, rhs),
-            0b1000 => Self::SurroundingSquaresEqual(RegConst::Constant(ext)),
-            0b1001 => Self::SurroundingSquaresNotEqual(RegConst::Constant(ext)),
-            0b1010 => Self::SurroundingSquaresGreater(RegConst::Constant(ext)),
-            0b1011 => Self::SurroundingSquaresLesser(RegConst::Constant(ext)),
-            0b1100 => Self::SurroundingSquaresGreaterEqual(RegConst::Constant(ext)),
-            0b1101 => Self::SurroundingSquaresLesserEqual(RegConst::Constant(ext)),
-            0b10000 => Self::SurroundingSquaresEqual(RegConst::Register(lhs)),
-            0b10001 => Self::SurroundingSquaresNotEqual(RegConst::Register(lhs)),
-            0b10010 => Self::SurroundingSquaresGreater(RegConst::Register(lhs)),
-            0b10011 => Self::SurroundingSquaresLesser(RegConst::Register(lhs)),
-            0b10100 => Self::SurroundingSquaresGreaterEqual(RegConst::Register(lhs)),
-            0b10101 => Self::SurroundingSquaresLesserEqual(RegConst::Register(lhs)),
-            _ => Self::Unconditional
-        }
-    }
-}
-
-impl From<u16> for Event {
-    fn from(value: u16) -> Self {
-        let op = ((value >> 8)&0xff) as u8;
-        let ext = (value&0xff) as u8;
-        Event::top_layer(op, ext)
-    }
-}
\ No newline at end of file
+use std::fmt::Display;
+
+use super::Direction;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Register {
+    LongRegister0,
+    LongRegister1,
+    ByteRegister0_0,
+    ByteRegister0_1,
+    ByteRegister0_2,
+    ByteRegister0_3,
+    ByteRegister1_0,
+    ByteRegister1_1,
+    ByteRegister1_2,
+    ByteRegister1_3,
+}
+/// a byte register index outside of the 3 bits used by REGBYTE.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidRegister(pub u8);
+impl Display for InvalidRegister {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#b} is not a byte register index", self.0)
+    }
+}
+impl std::error::Error for InvalidRegister {}
+/// converts a byte register index (%dr0_0 = 0b000 .. %dr1_3 = 0b111), anything above
+/// 0b111 is rejected instead of being masked.
+impl TryFrom<u8> for Register {
+    type Error = InvalidRegister;
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if value > 0b111 {
+            Err(InvalidRegister(value))
+        } else {
+            Ok(Self::from_byte_index(value))
+        }
+    }
+}
+impl Register {
+    pub const ALL: [Register; 10] = [
+        Register::LongRegister0, Register::LongRegister1,
+        Register::ByteRegister0_0, Register::ByteRegister0_1, Register::ByteRegister0_2, Register::ByteRegister0_3,
+        Register::ByteRegister1_0, Register::ByteRegister1_1, Register::ByteRegister1_2, Register::ByteRegister1_3,
+    ];
+    /// total decoding of a byte register index, only the lower 3 bits are read.
+    pub fn from_byte_index(value: u8) -> Self {
+        match value&0b111 {
+            0b0 => Self::ByteRegister0_0,
+            0b1 => Self::ByteRegister0_1,
+            0b10 => Self::ByteRegister0_2,
+            0b11 => Self::ByteRegister0_3,
+            0b100 => Self::ByteRegister1_0,
+            0b101 => Self::ByteRegister1_1,
+            0b110 => Self::ByteRegister1_2,
+            _ => Self::ByteRegister1_3,
+        }
+    }
+    pub fn is_long(&self) -> bool {
+        matches!(self, Register::LongRegister0 | Register::LongRegister1)
+    }
+    /// index of a byte register inside the REGBYTE ext field, `None` for the 64 bit registers.
+    fn byte_index(&self) -> Option<u8> {
+        match self {
+            Register::ByteRegister0_0 => Some(0b0),
+            Register::ByteRegister0_1 => Some(0b1),
+            Register::ByteRegister0_2 => Some(0b10),
+            Register::ByteRegister0_3 => Some(0b11),
+            Register::ByteRegister1_0 => Some(0b100),
+            Register::ByteRegister1_1 => Some(0b101),
+            Register::ByteRegister1_2 => Some(0b110),
+            Register::ByteRegister1_3 => Some(0b111),
+            Register::LongRegister0 | Register::LongRegister1 => None,
+        }
+    }
+}
+/// operand of an event or response. Decoding only ever produces constants from the
+/// ext byte and registers through the REGBYTE rules, so every value of the ext byte
+/// has a valid operand.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RegConst {
+    Register(Register),
+    Constant(u8),
+}
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Jump {
+    Unconditional(i8),
+    Reg0Eq(i8),
+    Reg0Neq(i8),
+    Reg0Greater(i8),
+    Reg0Lesser(i8),
+    Reg0GreaterEq(i8),
+    Reg0LesserEq(i8),
+    Reg1Eq(i8),
+    Reg1Neq(i8),
+    Reg1Greater(i8),
+    Reg1Lesser(i8),
+    Reg1GreaterEq(i8),
+    Reg1LesserEq(i8),
+}
+// Dst <- Src
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BinaryOp {
+    Add(Register, RegConst),
+    Sub(Register, RegConst),
+    Mul(Register, RegConst),
+    Div(Register, RegConst),
+    Xor(Register, RegConst),
+    And(Register, RegConst),
+    Or(Register, RegConst),
+    Mov(Register, RegConst),
+    Xchg(Register, Register),
+    MoveAdd(Register, RegConst),
+    MoveSub(Register, RegConst),
+    MoveMul(Register, RegConst),
+    MoveDiv(Register, RegConst),
+    MoveXor(Register, RegConst),
+    MoveAnd(Register, RegConst),
+    MoveOr(Register, RegConst),
+}
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Response {
+    Move(RegConst),
+    Jmp(Jump),
+    BinaryOp(BinaryOp),
+    Call(RegConst),
+    /// moves energy from the selected world layer at the current cell to the entity.
+    Harvest(u8),
+    /// adds the value of the register to the signal at the current cell.
+    Signal(Register),
+    Nop
+}
+/// what a [`Response`] does, without its operands.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ResponseKind {
+    Nop,
+    /// binary operations that write a register.
+    Arithmetic,
+    Jump,
+    /// `move` and the binary operations that move instead of writing a register.
+    Move,
+    Call,
+    Harvest,
+    Signal,
+}
+impl ResponseKind {
+    pub const ALL: [ResponseKind; 7] = [
+        ResponseKind::Nop, ResponseKind::Arithmetic, ResponseKind::Jump, ResponseKind::Move,
+        ResponseKind::Call, ResponseKind::Harvest, ResponseKind::Signal,
+    ];
+    pub fn name(&self) -> &'static str {
+        match self {
+            ResponseKind::Nop => "nop",
+            ResponseKind::Arithmetic => "arithmetic",
+            ResponseKind::Jump => "jump",
+            ResponseKind::Move => "move",
+            ResponseKind::Call => "call",
+            ResponseKind::Harvest => "harvest",
+            ResponseKind::Signal => "signal",
+        }
+    }
+}
+impl Display for ResponseKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+impl std::str::FromStr for ResponseKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ResponseKind::ALL.into_iter().find(|kind| kind.name() == s).ok_or_else(|| {
+            let names = ResponseKind::ALL.map(|kind| kind.name());
+            format!("expected one of {}, found '{s}'", names.join(", "))
+        })
+    }
+}
+fn regbyte_lhs_rhs_ext(ext: u8) -> (Register, Register) {
+    if (ext&0b10000000) != 0 { // Is 64 bits
+        if (ext&0b01000000) != 0 {
+            (Register::LongRegister1, Register::LongRegister0)                
+        } else {
+            (Register::LongRegister0, Register::LongRegister1)                
+        }
+    } else { // Is 8 bits
+        (Register::from_byte_index(ext>>3), Register::from_byte_index(ext))
+    }
+}
+/// reasons a value has no encoding, decoding never produces these values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncodeError {
+    /// the 64 bit REGBYTE form can only express the pairs (%dr0, %dr1) and (%dr1, %dr0),
+    /// and registers of different sizes can not be mixed.
+    RegisterPair(Register, Register),
+    /// no opcode decodes into this response, e.g. a binary operation with a constant
+    /// operand or a call through a byte register.
+    Response(Response),
+    /// surrounding square events can select one of [`NEIGHBORHOOD_SELECTORS`]
+    /// neighborhoods.
+    Neighborhood(u8),
+    /// layer events can select one of [`LAYER_SELECTORS`] layers.
+    Layer(u8),
+    /// directional signal events share the ext byte between the direction and the
+    /// constant, leaving room for constants below [`DIRECTIONAL_SIGNAL_CONSTANTS`].
+    SignalConstant(u8),
+}
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EncodeError::RegisterPair(lhs, rhs) => write!(f, "registers {lhs} and {rhs} can not be encoded as a pair"),
+            EncodeError::Response(response) => write!(f, "'{response}' has no encoding"),
+            EncodeError::Neighborhood(selector) => write!(f, "neighborhood {selector} is not below {NEIGHBORHOOD_SELECTORS}"),
+            EncodeError::Layer(selector) => write!(f, "layer {selector} is not below {LAYER_SELECTORS}"),
+            EncodeError::SignalConstant(constant) => write!(f, "directional signal constant {constant} is not below {DIRECTIONAL_SIGNAL_CONSTANTS}"),
+        }
+    }
+}
+impl std::error::Error for EncodeError {}
+/// inverse of [`regbyte_lhs_rhs_ext`].
+fn regbyte_ext(lhs: Register, rhs: Register) -> Result<u8, EncodeError> {
+    match (lhs, rhs) {
+        (Register::LongRegister0, Register::LongRegister1) => Ok(0b10000000),
+        (Register::LongRegister1, Register::LongRegister0) => Ok(0b11000000),
+        _ => match (lhs.byte_index(), rhs.byte_index()) {
+            (Some(lhs), Some(rhs)) => Ok((lhs<<3)|rhs),
+            _ => Err(EncodeError::RegisterPair(lhs, rhs)),
+        }
+    }
+}
+/// ext byte for opcodes that only read the lhs half of a REGBYTE.
+fn regbyte_lhs_ext(lhs: Register) -> u8 {
+    match lhs {
+        Register::LongRegister0 => 0b10000000,
+        Register::LongRegister1 => 0b11000000,
+        _ => lhs.byte_index().unwrap()<<3,
+    }
+}
+impl Response {
+    pub fn is_move_step(&self) -> bool {
+        matches!(self, Response::Move(_) |
+            Response::BinaryOp(BinaryOp::MoveAdd(_, _)) |
+            Response::BinaryOp(BinaryOp::MoveSub(_, _)) |
+            Response::BinaryOp(BinaryOp::MoveMul(_, _)) |
+            Response::BinaryOp(BinaryOp::MoveDiv(_, _)) |
+            Response::BinaryOp(BinaryOp::MoveAnd(_, _)) |
+            Response::BinaryOp(BinaryOp::MoveXor(_, _)) |
+            Response::BinaryOp(BinaryOp::MoveOr(_, _)))
+    }
+    pub fn kind(&self) -> ResponseKind {
+        match self {
+            Response::Nop => ResponseKind::Nop,
+            Response::Jmp(_) => ResponseKind::Jump,
+            Response::Move(_) => ResponseKind::Move,
+            Response::BinaryOp(_) if self.is_move_step() => ResponseKind::Move,
+            Response::BinaryOp(_) => ResponseKind::Arithmetic,
+            Response::Call(_) => ResponseKind::Call,
+            Response::Harvest(_) => ResponseKind::Harvest,
+            Response::Signal(_) => ResponseKind::Signal,
+        }
+    }
+    fn top_layer(op: u8, ext: u8) -> Self {
+        match op {
+            0b0 =>          Self::Move(RegConst::Register(Register::LongRegister0)),
+            0b1 =>          Self::Move(RegConst::Register(Register::LongRegister1)),
+            0b10 =>         Self::Call(RegConst::Register(Register::LongRegister0)),
+            0b11 =>         Self::Call(RegConst::Register(Register::LongRegister1)),
+            0b101 =>        Self::Harvest(ext),
+            0b110 =>        Self::Signal(regbyte_lhs_rhs_ext(ext).0),
+            0b01000 =>      Self::Jmp(Jump::Reg0Eq(ext as i8)),
+            0b01001 =>      Self::Jmp(Jump::Reg0Neq(ext as i8)),
+            0b01010 =>      Self::Jmp(Jump::Reg0Greater(ext as i8)),
+            0b01011 =>      Self::Jmp(Jump::Reg0Lesser(ext as i8)),
+            0b01100 =>      Self::Jmp(Jump::Reg0GreaterEq(ext as i8)),
+            0b01101 =>      Self::Jmp(Jump::Reg0LesserEq(ext as i8)),
+            0b1110 =>       Self::Jmp(Jump::Unconditional(ext as i8)),
+            0b1111 =>       Self::Move(RegConst::Constant(ext)),
+            0b100000 =>     Self::Jmp(Jump::Reg1Eq(ext as i8)),
+            0b100001 =>     Self::Jmp(Jump::Reg1Neq(ext as i8)),
+            0b100010 =>     Self::Jmp(Jump::Reg1Greater(ext as i8)),
+            0b100011 =>     Self::Jmp(Jump::Reg1Lesser(ext as i8)),
+            0b100100 =>     Self::Jmp(Jump::Reg1GreaterEq(ext as i8)),
+            0b100101 =>     Self::Jmp(Jump::Reg1LesserEq(ext as i8)),
+            0b1000000 =>    Self::Move(RegConst::Register(Register::LongRegister0)),
+            0b1000001 =>    Self::Move(RegConst::Register(Register::LongRegister1)),
+            0b1100000 =>    Self::Move(RegConst::Register(Register::LongRegister0)),
+            0b1100001 =>    Self::Move(RegConst::Register(Register::LongRegister1)),
+            0b10000000 =>   Self::Move(RegConst::Register(Register::LongRegister0)),
+            0b10000001 =>   Self::Move(RegConst::Register(Register::LongRegister1)),
+            0b11000000 =>   Self::Move(RegConst::Register(Register::LongRegister0)),
+            0b11000001 =>   Self::Move(RegConst::Register(Register::LongRegister1)),
+            // 0b11100000 =>   Self::Move(RegConst::Register(Register::LongRegister0)),
+            // 0b11100001 =>   Self::Move(RegConst::Register(Register::LongRegister1)),
+            _ => {
+                if (op&0b10000) != 0 {
+                    let (lhs, rhs) = regbyte_lhs_rhs_ext(ext);
+                    match op {
+                        0b10000 => Self::BinaryOp(BinaryOp::Add(lhs, RegConst::Register(rhs))),
+                        0b10001 => Self::BinaryOp(BinaryOp::Sub(lhs, RegConst::Register(rhs))),
+                        0b10010 => Self::BinaryOp(BinaryOp::Mul(lhs, RegConst::Register(rhs))),
+                        0b10011 => Self::BinaryOp(BinaryOp::Div(lhs, RegConst::Register(rhs))),
+                        0b10100 => Self::BinaryOp(BinaryOp::Xor(lhs, RegConst::Register(rhs))),
+                        0b10101 => Self::BinaryOp(BinaryOp::And(lhs, RegConst::Register(rhs))),
+                        0b10110 => Self::BinaryOp(BinaryOp::Or(lhs, RegConst::Register(rhs))),
+                        0b10111 => Self::BinaryOp(BinaryOp::Mov(lhs, RegConst::Register(rhs))),
+                        0b11111 => Self::BinaryOp(BinaryOp::Xchg(lhs, rhs)),
+                        0b110000 => Self::BinaryOp(BinaryOp::MoveAdd(lhs, RegConst::Register(rhs))),
+                        0b110001 => Self::BinaryOp(BinaryOp::MoveSub(lhs, RegConst::Register(rhs))),
+                        0b110010 => Self::BinaryOp(BinaryOp::MoveMul(lhs, RegConst::Register(rhs))),
+                        0b110011 => Self::BinaryOp(BinaryOp::MoveDiv(lhs, RegConst::Register(rhs))),
+                        0b110100 => Self::BinaryOp(BinaryOp::MoveXor(lhs, RegConst::Register(rhs))),
+                        0b110101 => Self::BinaryOp(BinaryOp::MoveAnd(lhs, RegConst::Register(rhs))),
+                        0b110110 => Self::BinaryOp(BinaryOp::MoveOr(lhs, RegConst::Register(rhs))),
+                        0b111000 => Self::Move(RegConst::Constant(0)),
+                        0b111001 => Self::Move(RegConst::Constant(4)),
+                        0b111010 => Self::Move(RegConst::Constant(2)),
+                        0b111011 => Self::Move(RegConst::Constant(6)),
+                        _ => Response::Nop,
+                    }
+                } else if (op&0b10000000) != 0 {
+                    match op {
+                        0b10000000 => Self::Call(RegConst::Register(Register::LongRegister0)),
+                        0b10000001 => Self::Call(RegConst::Register(Register::LongRegister1)),
+                        0b11111111 => Self::Call(RegConst::Constant(ext)),
+                        _ => Response::Nop,
+                    }
+                } else {
+                    Response::Nop
+                }
+            }
+        }
+    }
+}
+impl Response {
+    /// encodes the response into the canonical 16 bits read by [`Response::from`], so
+    /// that `Response::from(response.encode()?) == response`.
+    pub fn encode(&self) -> Result<u16, EncodeError> {
+        let unreachable = EncodeError::Response(*self);
+        let (op, ext): (u8, u8) = match *self {
+            Self::Move(RegConst::Register(Register::LongRegister0)) => (0b0, 0),
+            Self::Move(RegConst::Register(Register::LongRegister1)) => (0b1, 0),
+            Self::Move(RegConst::Register(_)) => return Err(unreachable),
+            Self::Move(RegConst::Constant(ext)) => (0b1111, ext),
+            Self::Call(RegConst::Register(Register::LongRegister0)) => (0b10, 0),
+            Self::Call(RegConst::Register(Register::LongRegister1)) => (0b11, 0),
+            // 0b11111111 is caught by the REGBYTE branch of `top_layer` and decodes to a Nop
+            Self::Call(_) => return Err(unreachable),
+            Self::Jmp(jump) => match jump {
+                Jump::Reg0Eq(ext) => (0b01000, ext as u8),
+                Jump::Reg0Neq(ext) => (0b01001, ext as u8),
+                Jump::Reg0Greater(ext) => (0b01010, ext as u8),
+                Jump::Reg0Lesser(ext) => (0b01011, ext as u8),
+                Jump::Reg0GreaterEq(ext) => (0b01100, ext as u8),
+                Jump::Reg0LesserEq(ext) => (0b01101, ext as u8),
+                Jump::Unconditional(ext) => (0b1110, ext as u8),
+                Jump::Reg1Eq(ext) => (0b100000, ext as u8),
+                Jump::Reg1Neq(ext) => (0b100001, ext as u8),
+                Jump::Reg1Greater(ext) => (0b100010, ext as u8),
+                Jump::Reg1Lesser(ext) => (0b100011, ext as u8),
+                Jump::Reg1GreaterEq(ext) => (0b100100, ext as u8),
+                Jump::Reg1LesserEq(ext) => (0b100101, ext as u8),
+            },
+            Self::BinaryOp(BinaryOp::Xchg(lhs, rhs)) => (0b11111, regbyte_ext(lhs, rhs)?),
+            Self::BinaryOp(op) => {
+                let (op, lhs, rhs) = match op {
+                    BinaryOp::Add(lhs, rhs) => (0b10000, lhs, rhs),
+                    BinaryOp::Sub(lhs, rhs) => (0b10001, lhs, rhs),
+                    BinaryOp::Mul(lhs, rhs) => (0b10010, lhs, rhs),
+                    BinaryOp::Div(lhs, rhs) => (0b10011, lhs, rhs),
+                    BinaryOp::Xor(lhs, rhs) => (0b10100, lhs, rhs),
+                    BinaryOp::And(lhs, rhs) => (0b10101, lhs, rhs),
+                    BinaryOp::Or(lhs, rhs) => (0b10110, lhs, rhs),
+                    BinaryOp::Mov(lhs, rhs) => (0b10111, lhs, rhs),
+                    BinaryOp::MoveAdd(lhs, rhs) => (0b110000, lhs, rhs),
+                    BinaryOp::MoveSub(lhs, rhs) => (0b110001, lhs, rhs),
+                    BinaryOp::MoveMul(lhs, rhs) => (0b110010, lhs, rhs),
+                    BinaryOp::MoveDiv(lhs, rhs) => (0b110011, lhs, rhs),
+                    BinaryOp::MoveXor(lhs, rhs) => (0b110100, lhs, rhs),
+                    BinaryOp::MoveAnd(lhs, rhs) => (0b110101, lhs, rhs),
+                    BinaryOp::MoveOr(lhs, rhs) => (0b110110, lhs, rhs),
+                    BinaryOp::Xchg(_, _) => unreachable!(),
+                };
+                // binary operations only ever decode with a register on the rhs
+                let RegConst::Register(rhs) = rhs else { return Err(unreachable); };
+                (op, regbyte_ext(lhs, rhs)?)
+            }
+            Self::Harvest(layer) => (0b101, layer),
+            Self::Signal(reg) => (0b110, regbyte_lhs_ext(reg)),
+            Self::Nop => (0b100, 0),
+        };
+        Ok(((op as u16) << 8)|ext as u16)
+    }
+}
+/// decoding is total, every one of the 65536 values is a valid response. Opcodes
+/// that are not assigned decode to [`Response::Nop`].
+impl From<u16> for Response {
+    fn from(value: u16) -> Self {
+        let op = ((value >> 8)&0xff) as u8;
+        let ext = (value&0xff) as u8;
+        Response::top_layer(op, ext)
+    }
+}
+/// number of neighborhoods a surrounding square event can select, the selector is
+/// stored in the top three bits of the opcode.
+pub const NEIGHBORHOOD_SELECTORS: u8 = 8;
+/// number of world layers a layer event can select, the selector is stored in bits 5
+/// and 6 of the opcode while bit 7 marks the register form.
+pub const LAYER_SELECTORS: u8 = 4;
+/// directional signal events compared against a constant keep the direction in the
+/// low three bits of the ext byte and the constant in the other five.
+pub const DIRECTIONAL_SIGNAL_CONSTANTS: u8 = 32;
+/// Events generate boolean values to see whether a respone
+/// should be executed or not. Every single Response needs an
+/// Event.
+///
+/// The surrounding square events carry the index of the world neighborhood they
+/// count in, see `World::neighborhood`, and the layer events the index of the world
+/// layer they read at the current cell. The signal events read the signal at the
+/// current cell or, given a direction, at the neighboring cell.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Event {
+    Unconditional,
+    Equal(Register, Register),
+    NotEqual(Register, Register),
+    Greater(Register, Register),
+    Lesser(Register, Register),
+    GreaterEqual(Register, Register),
+    LesserEqual(Register, Register),
+    SurroundingSquaresEqual(RegConst, u8),
+    SurroundingSquaresNotEqual(RegConst, u8),
+    SurroundingSquaresGreater(RegConst, u8),
+    SurroundingSquaresLesser(RegConst, u8),
+    SurroundingSquaresGreaterEqual(RegConst, u8),
+    SurroundingSquaresLesserEqual(RegConst, u8),
+    LayerEqual(RegConst, u8),
+    LayerNotEqual(RegConst, u8),
+    LayerGreater(RegConst, u8),
+    LayerLesser(RegConst, u8),
+    LayerGreaterEqual(RegConst, u8),
+    LayerLesserEqual(RegConst, u8),
+    SignalEqual(RegConst, Option<Direction>),
+    SignalNotEqual(RegConst, Option<Direction>),
+    SignalGreater(RegConst, Option<Direction>),
+    SignalLesser(RegConst, Option<Direction>),
+    SignalGreaterEqual(RegConst, Option<Direction>),
+    SignalLesserEqual(RegConst, Option<Direction>),
+}
+
+impl Event {
+    fn top_layer(op: u8, ext: u8) -> Self {
+        let (lhs, rhs) = regbyte_lhs_rhs_ext(ext);
+        match op {
+            0b0 => Self::Equal(lhs, rhs),
+            0b1 => Self::NotEqual(lhs, rhs),
+            0b10 => Self::Greater(lhs, rhs),
+            0b11 => Self::Lesser(lhs, rhs),
+            0b100 => Self::GreaterEqual(lhs, rhs),
+            0b101 => Self::LesserEqual(lhs, rhs),
+            _ => match op&0b11111 {
+                0b0..=0b101 => Self::signal_event(op, ext),
+                0b11000..=0b11101 => Self::layer_event(op, ext),
+                _ => Self::surrounding_layer(op, ext),
+            },
+        }
+    }
+    /// the low five bits of the opcode pick the comparison and the top three where the
+    /// signal is read and what it is compared against:
+    ///
+    /// * 0b001 the current cell against a constant.
+    /// * 0b010 the current cell against the REGBYTE lhs register.
+    /// * 0b011 a neighboring cell against the REGBYTE lhs register, the direction is
+    ///   in the low three bits of the ext byte.
+    /// * 0b100 a neighboring cell against a constant, the direction is in the low
+    ///   three bits of the ext byte and the constant in the other five.
+    fn signal_event(op: u8, ext: u8) -> Self {
+        let direction = Some(Direction::from((ext&0b111) as u64));
+        let (lhs, direction) = match op >> 5 {
+            0b001 => (RegConst::Constant(ext), None),
+            0b010 => (RegConst::Register(regbyte_lhs_rhs_ext(ext).0), None),
+            0b011 => (RegConst::Register(regbyte_lhs_rhs_ext(ext).0), direction),
+            0b100 => (RegConst::Constant(ext >> 3), direction),
+            _ => return Self::Unconditional,
+        };
+        match op&0b11111 {
+            0b0 => Self::SignalEqual(lhs, direction),
+            0b1 => Self::SignalNotEqual(lhs, direction),
+            0b10 => Self::SignalGreater(lhs, direction),
+            0b11 => Self::SignalLesser(lhs, direction),
+            0b100 => Self::SignalGreaterEqual(lhs, direction),
+            _ => Self::SignalLesserEqual(lhs, direction),
+        }
+    }
+    /// the low five bits of the opcode pick the comparison, bits 5 and 6 the layer and
+    /// bit 7 whether the operand is a constant or a register.
+    fn layer_event(op: u8, ext: u8) -> Self {
+        let layer = (op >> 5)&0b11;
+        let lhs = if (op&0b10000000) != 0 {
+            RegConst::Register(regbyte_lhs_rhs_ext(ext).0)
+        } else {
+            RegConst::Constant(ext)
+        };
+        match op&0b11111 {
+            0b11000 => Self::LayerEqual(lhs, layer),
+            0b11001 => Self::LayerNotEqual(lhs, layer),
+            0b11010 => Self::LayerGreater(lhs, layer),
+            0b11011 => Self::LayerLesser(lhs, layer),
+            0b11100 => Self::LayerGreaterEqual(lhs, layer),
+            _ => Self::LayerLesserEqual(lhs, layer),
+        }
+    }
+    /// the low five bits of the opcode pick the comparison, the top three the
+    /// neighborhood.
+    fn surrounding_layer(op: u8, ext: u8) -> Self {
+        let (lhs, _) = regbyte_lhs_rhs_ext(ext);
+        let neighborhood = op >> 5;
+        match op&0b11111 {
+            0b1000 => Self::SurroundingSquaresEqual(RegConst::Constant(ext), neighborhood),
+            0b1001 => Self::SurroundingSquaresNotEqual(RegConst::Constant(ext), neighborhood),
+            0b1010 => Self::SurroundingSquaresGreater(RegConst::Constant(ext), neighborhood),
+            0b1011 => Self::SurroundingSquaresLesser(RegConst::Constant(ext), neighborhood),
+            0b1100 => Self::SurroundingSquaresGreaterEqual(RegConst::Constant(ext), neighborhood),
+            0b1101 => Self::SurroundingSquaresLesserEqual(RegConst::Constant(ext), neighborhood),
+            0b10000 => Self::SurroundingSquaresEqual(RegConst::Register(lhs), neighborhood),
+            0b10001 => Self::SurroundingSquaresNotEqual(RegConst::Register(lhs), neighborhood),
+            0b10010 => Self::SurroundingSquaresGreater(RegConst::Register(lhs), neighborhood),
+            0b10011 => Self::SurroundingSquaresLesser(RegConst::Register(lhs), neighborhood),
+            0b10100 => Self::SurroundingSquaresGreaterEqual(RegConst::Register(lhs), neighborhood),
+            0b10101 => Self::SurroundingSquaresLesserEqual(RegConst::Register(lhs), neighborhood),
+            _ => Self::Unconditional
+        }
+    }
+}
+
+impl Event {
+    /// encodes the event into the canonical 16 bits read by [`Event::from`], so that
+    /// `Event::from(event.encode()?) == event`.
+    pub fn encode(&self) -> Result<u16, EncodeError> {
+        let (op, ext): (u8, u8) = match *self {
+            Self::Unconditional => (0b110, 0),
+            Self::Equal(lhs, rhs) => (0b0, regbyte_ext(lhs, rhs)?),
+            Self::NotEqual(lhs, rhs) => (0b1, regbyte_ext(lhs, rhs)?),
+            Self::Greater(lhs, rhs) => (0b10, regbyte_ext(lhs, rhs)?),
+            Self::Lesser(lhs, rhs) => (0b11, regbyte_ext(lhs, rhs)?),
+            Self::GreaterEqual(lhs, rhs) => (0b100, regbyte_ext(lhs, rhs)?),
+            Self::LesserEqual(lhs, rhs) => (0b101, regbyte_ext(lhs, rhs)?),
+            Self::SurroundingSquaresEqual(lhs, neighborhood) => Self::surrounding_ext(0b1000, lhs, neighborhood)?,
+            Self::SurroundingSquaresNotEqual(lhs, neighborhood) => Self::surrounding_ext(0b1001, lhs, neighborhood)?,
+            Self::SurroundingSquaresGreater(lhs, neighborhood) => Self::surrounding_ext(0b1010, lhs, neighborhood)?,
+            Self::SurroundingSquaresLesser(lhs, neighborhood) => Self::surrounding_ext(0b1011, lhs, neighborhood)?,
+            Self::SurroundingSquaresGreaterEqual(lhs, neighborhood) => Self::surrounding_ext(0b1100, lhs, neighborhood)?,
+            Self::SurroundingSquaresLesserEqual(lhs, neighborhood) => Self::surrounding_ext(0b1101, lhs, neighborhood)?,
+            Self::LayerEqual(lhs, layer) => Self::layer_ext(0b11000, lhs, layer)?,
+            Self::LayerNotEqual(lhs, layer) => Self::layer_ext(0b11001, lhs, layer)?,
+            Self::LayerGreater(lhs, layer) => Self::layer_ext(0b11010, lhs, layer)?,
+            Self::LayerLesser(lhs, layer) => Self::layer_ext(0b11011, lhs, layer)?,
+            Self::LayerGreaterEqual(lhs, layer) => Self::layer_ext(0b11100, lhs, layer)?,
+            Self::LayerLesserEqual(lhs, layer) => Self::layer_ext(0b11101, lhs, layer)?,
+            Self::SignalEqual(lhs, direction) => Self::signal_ext(0b0, lhs, direction)?,
+            Self::SignalNotEqual(lhs, direction) => Self::signal_ext(0b1, lhs, direction)?,
+            Self::SignalGreater(lhs, direction) => Self::signal_ext(0b10, lhs, direction)?,
+            Self::SignalLesser(lhs, direction) => Self::signal_ext(0b11, lhs, direction)?,
+            Self::SignalGreaterEqual(lhs, direction) => Self::signal_ext(0b100, lhs, direction)?,
+            Self::SignalLesserEqual(lhs, direction) => Self::signal_ext(0b101, lhs, direction)?,
+        };
+        Ok(((op as u16) << 8)|ext as u16)
+    }
+    /// the register forms of the surrounding square events live 8 opcodes above
+    /// the constant forms.
+    fn surrounding_ext(op: u8, lhs: RegConst, neighborhood: u8) -> Result<(u8, u8), EncodeError> {
+        if neighborhood >= NEIGHBORHOOD_SELECTORS {
+            return Err(EncodeError::Neighborhood(neighborhood));
+        }
+        let op = op|(neighborhood << 5);
+        Ok(match lhs {
+            RegConst::Constant(ext) => (op, ext),
+            RegConst::Register(reg) => (op+0b1000, regbyte_lhs_ext(reg)),
+        })
+    }
+    fn signal_ext(op: u8, lhs: RegConst, direction: Option<Direction>) -> Result<(u8, u8), EncodeError> {
+        Ok(match (lhs, direction) {
+            (RegConst::Constant(ext), None) => (op|0b00100000, ext),
+            (RegConst::Register(reg), None) => (op|0b01000000, regbyte_lhs_ext(reg)),
+            (RegConst::Register(reg), Some(direction)) => (op|0b01100000, regbyte_lhs_ext(reg)|direction as u8),
+            (RegConst::Constant(constant), Some(direction)) => {
+                if constant >= DIRECTIONAL_SIGNAL_CONSTANTS {
+                    return Err(EncodeError::SignalConstant(constant));
+                }
+                (op|0b10000000, (constant << 3)|direction as u8)
+            }
+        })
+    }
+    fn layer_ext(op: u8, lhs: RegConst, layer: u8) -> Result<(u8, u8), EncodeError> {
+        if layer >= LAYER_SELECTORS {
+            return Err(EncodeError::Layer(layer));
+        }
+        let op = op|(layer << 5);
+        Ok(match lhs {
+            RegConst::Constant(ext) => (op, ext),
+            RegConst::Register(reg) => (op|0b10000000, regbyte_lhs_ext(reg)),
+        })
+    }
+}
+/// decoding is total, every one of the 65536 values is a valid event. Opcodes that
+/// are not assigned decode to [`Event::Unconditional`].
+impl From<u16> for Event {
+    fn from(value: u16) -> Self {
+        let op = ((value >> 8)&0xff) as u8;
+        let ext = (value&0xff) as u8;
+        Event::top_layer(op, ext)
+    }
+}
+
+impl Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Register::LongRegister0 => "r0",
+            Register::LongRegister1 => "r1",
+            Register::ByteRegister0_0 => "r0.b0",
+            Register::ByteRegister0_1 => "r0.b1",
+            Register::ByteRegister0_2 => "r0.b2",
+            Register::ByteRegister0_3 => "r0.b3",
+            Register::ByteRegister1_0 => "r1.b0",
+            Register::ByteRegister1_1 => "r1.b1",
+            Register::ByteRegister1_2 => "r1.b2",
+            Register::ByteRegister1_3 => "r1.b3",
+        })
+    }
+}
+/// reads the names printed by [`Register`]'s `Display`, e.g. `r1` or `r0.b2`.
+impl std::str::FromStr for Register {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Register::ALL.into_iter().find(|register| register.to_string() == s).ok_or_else(|| format!("'{s}' is not a register"))
+    }
+}
+impl Display for RegConst {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegConst::Register(reg) => reg.fmt(f),
+            RegConst::Constant(constant) => constant.fmt(f),
+        }
+    }
+}
+impl Jump {
+    pub fn offset(&self) -> i8 {
+        let (Jump::Unconditional(jmp) | Jump::Reg0Eq(jmp) | Jump::Reg0Neq(jmp) |
+            Jump::Reg0Greater(jmp) | Jump::Reg0Lesser(jmp) | Jump::Reg0GreaterEq(jmp) |
+            Jump::Reg0LesserEq(jmp) | Jump::Reg1Eq(jmp) | Jump::Reg1Neq(jmp) |
+            Jump::Reg1Greater(jmp) | Jump::Reg1Lesser(jmp) | Jump::Reg1GreaterEq(jmp) |
+            Jump::Reg1LesserEq(jmp)) = *self;
+        jmp
+    }
+    /// index of the instruction executed after taking this jump from the instruction at
+    /// `idx` in a program of `len` instructions.
+    ///
+    /// the offset is applied to the already incremented instruction pointer and wrapped
+    /// with a signed modulo, a negative result can not be fetched so execution restarts
+    /// at 0.
+    pub fn target(&self, idx: usize, len: usize) -> usize {
+        let jmp_loc = (self.offset() as isize + idx as isize + 1)%len as isize;
+        if jmp_loc < 0 { 0 } else { jmp_loc as usize }
+    }
+}
+impl Display for Jump {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (mnemonic, reg) = match self {
+            Jump::Unconditional(_) => return write!(f, "jmp {}", self.offset()),
+            Jump::Reg0Eq(_) => ("je", Register::LongRegister0),
+            Jump::Reg0Neq(_) => ("jne", Register::LongRegister0),
+            Jump::Reg0Greater(_) => ("jg", Register::LongRegister0),
+            // the lesser jumps compare like their lesser or equal forms
+            Jump::Reg0Lesser(_) => ("jle", Register::LongRegister0),
+            Jump::Reg0GreaterEq(_) => ("jge", Register::LongRegister0),
+            Jump::Reg0LesserEq(_) => ("jle", Register::LongRegister0),
+            Jump::Reg1Eq(_) => ("je", Register::LongRegister1),
+            Jump::Reg1Neq(_) => ("jne", Register::LongRegister1),
+            Jump::Reg1Greater(_) => ("jg", Register::LongRegister1),
+            Jump::Reg1Lesser(_) => ("jle", Register::LongRegister1),
+            Jump::Reg1GreaterEq(_) => ("jge", Register::LongRegister1),
+            Jump::Reg1LesserEq(_) => ("jle", Register::LongRegister1),
+        };
+        write!(f, "{mnemonic} {reg}, {}", self.offset())
+    }
+}
+impl Display for BinaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (mnemonic, lhs, rhs) = match self {
+            BinaryOp::Add(lhs, rhs) => ("add", lhs, *rhs),
+            BinaryOp::Sub(lhs, rhs) => ("sub", lhs, *rhs),
+            BinaryOp::Mul(lhs, rhs) => ("mul", lhs, *rhs),
+            BinaryOp::Div(lhs, rhs) => ("div", lhs, *rhs),
+            BinaryOp::Xor(lhs, rhs) => ("xor", lhs, *rhs),
+            BinaryOp::And(lhs, rhs) => ("and", lhs, *rhs),
+            BinaryOp::Or(lhs, rhs) => ("or", lhs, *rhs),
+            BinaryOp::Mov(lhs, rhs) => ("mov", lhs, *rhs),
+            BinaryOp::Xchg(lhs, rhs) => ("xchg", lhs, RegConst::Register(*rhs)),
+            BinaryOp::MoveAdd(lhs, rhs) => ("madd", lhs, *rhs),
+            BinaryOp::MoveSub(lhs, rhs) => ("msub", lhs, *rhs),
+            BinaryOp::MoveMul(lhs, rhs) => ("mmul", lhs, *rhs),
+            BinaryOp::MoveDiv(lhs, rhs) => ("mdiv", lhs, *rhs),
+            BinaryOp::MoveXor(lhs, rhs) => ("mxor", lhs, *rhs),
+            BinaryOp::MoveAnd(lhs, rhs) => ("mand", lhs, *rhs),
+            BinaryOp::MoveOr(lhs, rhs) => ("mor", lhs, *rhs),
+        };
+        write!(f, "{mnemonic} {lhs}, {rhs}")
+    }
+}
+/// prints the response in the syntax read by [`super::asm::assemble`].
+impl Display for Response {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Response::Move(reg) => write!(f, "move {reg}"),
+            Response::Jmp(jump) => jump.fmt(f),
+            Response::BinaryOp(op) => op.fmt(f),
+            Response::Call(reg) => write!(f, "call {reg}"),
+            Response::Harvest(layer) => write!(f, "harvest {layer}"),
+            Response::Signal(reg) => write!(f, "signal {reg}"),
+            Response::Nop => f.write_str("nop"),
+        }
+    }
+}
+/// the surrounding square count, `surround` for the first neighborhood and
+/// `surround.n` for the others.
+struct Surround(u8);
+impl Display for Surround {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            0 => f.write_str("surround"),
+            neighborhood => write!(f, "surround.{neighborhood}"),
+        }
+    }
+}
+/// the value of a world layer at the current cell, `layer` for the first layer and
+/// `layer.n` for the others.
+struct LayerValue(u8);
+impl Display for LayerValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            0 => f.write_str("layer"),
+            layer => write!(f, "layer.{layer}"),
+        }
+    }
+}
+/// the signal at the current cell, `signal`, or next to it, e.g. `signal.top_left`.
+struct SignalAt(Option<Direction>);
+impl Display for SignalAt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            None => f.write_str("signal"),
+            Some(direction) => write!(f, "signal.{}", direction.name()),
+        }
+    }
+}
+/// prints the event as the `if ... :` prefix read by [`super::asm::assemble`]. The
+/// unconditional event prints nothing.
+impl Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Event::Unconditional => Ok(()),
+            Event::Equal(lhs, rhs) => write!(f, "if {lhs} == {rhs} :"),
+            Event::NotEqual(lhs, rhs) => write!(f, "if {lhs} != {rhs} :"),
+            Event::Greater(lhs, rhs) => write!(f, "if {lhs} > {rhs} :"),
+            Event::Lesser(lhs, rhs) => write!(f, "if {lhs} < {rhs} :"),
+            Event::GreaterEqual(lhs, rhs) => write!(f, "if {lhs} >= {rhs} :"),
+            Event::LesserEqual(lhs, rhs) => write!(f, "if {lhs} <= {rhs} :"),
+            // the surrounding square events compare the operand against the count, so
+            // the comparison is flipped to read with the count on the left.
+            Event::SurroundingSquaresEqual(lhs, neighborhood) => write!(f, "if {} == {lhs} :", Surround(*neighborhood)),
+            Event::SurroundingSquaresNotEqual(lhs, neighborhood) => write!(f, "if {} != {lhs} :", Surround(*neighborhood)),
+            Event::SurroundingSquaresGreater(lhs, neighborhood) => write!(f, "if {} < {lhs} :", Surround(*neighborhood)),
+            Event::SurroundingSquaresLesser(lhs, neighborhood) => write!(f, "if {} > {lhs} :", Surround(*neighborhood)),
+            Event::SurroundingSquaresGreaterEqual(lhs, neighborhood) => write!(f, "if {} <= {lhs} :", Surround(*neighborhood)),
+            Event::SurroundingSquaresLesserEqual(lhs, neighborhood) => write!(f, "if {} >= {lhs} :", Surround(*neighborhood)),
+            // the layer events compare the same way as the surrounding square events
+            Event::LayerEqual(lhs, layer) => write!(f, "if {} == {lhs} :", LayerValue(*layer)),
+            Event::LayerNotEqual(lhs, layer) => write!(f, "if {} != {lhs} :", LayerValue(*layer)),
+            Event::LayerGreater(lhs, layer) => write!(f, "if {} < {lhs} :", LayerValue(*layer)),
+            Event::LayerLesser(lhs, layer) => write!(f, "if {} > {lhs} :", LayerValue(*layer)),
+            Event::LayerGreaterEqual(lhs, layer) => write!(f, "if {} <= {lhs} :", LayerValue(*layer)),
+            Event::LayerLesserEqual(lhs, layer) => write!(f, "if {} >= {lhs} :", LayerValue(*layer)),
+            Event::SignalEqual(lhs, direction) => write!(f, "if {} == {lhs} :", SignalAt(*direction)),
+            Event::SignalNotEqual(lhs, direction) => write!(f, "if {} != {lhs} :", SignalAt(*direction)),
+            Event::SignalGreater(lhs, direction) => write!(f, "if {} < {lhs} :", SignalAt(*direction)),
+            Event::SignalLesser(lhs, direction) => write!(f, "if {} > {lhs} :", SignalAt(*direction)),
+            Event::SignalGreaterEqual(lhs, direction) => write!(f, "if {} <= {lhs} :", SignalAt(*direction)),
+            Event::SignalLesserEqual(lhs, direction) => write!(f, "if {} >= {lhs} :", SignalAt(*direction)),
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DisassembledWord {
+    pub index: usize,
+    pub word: u32,
+    pub event: Event,
+    pub response: Response,
+    /// where execution continues if the response is a jump that is taken.
+    pub jump_target: Option<usize>,
+}
+impl DisassembledWord {
+    pub fn new(index: usize, word: u32, len: usize) -> Self {
+        let event = Event::from(((word >> 16)&0xffff) as u16);
+        let response = Response::from((word&0xffff) as u16);
+        let jump_target = match response {
+            Response::Jmp(jump) => Some(jump.target(index, len)),
+            _ => None,
+        };
+        Self { index, word, event, response, jump_target }
+    }
+    /// the event opcode is not assigned and fell through to [`Event::Unconditional`].
+    pub fn is_default_event(&self) -> bool {
+        self.event == Event::Unconditional
+    }
+    /// the response opcode is not assigned and fell through to [`Response::Nop`].
+    pub fn is_default_response(&self) -> bool {
+        self.response == Response::Nop
+    }
+}
+impl Display for DisassembledWord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:4}  {:08x}  ", self.index, self.word)?;
+        let instruction = if self.is_default_event() {
+            self.response.to_string()
+        } else {
+            format!("{} {}", self.event, self.response)
+        };
+        write!(f, "{instruction:40}")?;
+        let mut comments = vec![];
+        if let Some(target) = self.jump_target {
+            comments.push(format!("-> {target}"));
+        }
+        if self.is_default_event() {
+            comments.push("default event".to_string());
+        }
+        if self.is_default_response() {
+            comments.push("default response".to_string());
+        }
+        if !comments.is_empty() {
+            write!(f, "; {}", comments.join(", "))?;
+        }
+        Ok(())
+    }
+}
+/// decoded listing of a program, printing it gives one line per word.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Disassembly {
+    pub words: Vec<DisassembledWord>,
+}
+impl Disassembly {
+    /// words whose response fell through to [`Response::Nop`] and therefore never do
+    /// anything.
+    pub fn dead_words(&self) -> usize {
+        self.words.iter().filter(|word| word.is_default_response()).count()
+    }
+    pub fn default_events(&self) -> usize {
+        self.words.iter().filter(|word| word.is_default_event()).count()
+    }
+}
+impl Display for Disassembly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for word in self.words.iter() {
+            writeln!(f, "{word}")?;
+        }
+        write!(f, "; {} words, {} default responses, {} default events", self.words.len(), self.dead_words(), self.default_events())
+    }
+}
+pub fn disassemble(code: &[u32]) -> Disassembly {
+    Disassembly { words: code.iter().enumerate().map(|(index, word)| DisassembledWord::new(index, *word, code.len())).collect() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new2::entity::{asm::assemble, EventResponse, GPCAEntityInternal};
+
+    /// where the interpreter continues after taking `jump` from the instruction at
+    /// `idx` of a program of `len` instructions.
+    fn interpreted(jump: Jump, idx: usize, len: usize) -> usize {
+        let mut entity = GPCAEntityInternal::new(0, 0, 0, 0, 0);
+        entity.rip = idx+1;
+        entity.handle_response(Response::Jmp(jump), len);
+        entity.next_index(len)
+    }
+
+    #[test]
+    fn every_half_decodes() {
+        // decoding is total, any half that panics fails the test
+        let events = (0..=u16::MAX).map(Event::from).filter(|event| *event != Event::Unconditional).count();
+        let responses = (0..=u16::MAX).map(Response::from).filter(|response| *response != Response::Nop).count();
+        assert!(events > 0 && responses > 0);
+    }
+    #[test]
+    fn byte_register_indices_above_0b111_are_rejected() {
+        for value in 0..=u8::MAX {
+            match Register::try_from(value) {
+                Ok(register) => {
+                    assert!(value <= 0b111);
+                    assert_eq!(register, Register::from_byte_index(value));
+                    assert_eq!(register.byte_index(), Some(value));
+                }
+                Err(err) => {
+                    assert!(value > 0b111);
+                    assert_eq!(err, InvalidRegister(value));
+                }
+            }
+        }
+        assert_eq!(Register::try_from(0b1000), Err(InvalidRegister(0b1000)));
+    }
+    #[test]
+    fn jump_targets() {
+        assert_eq!(Jump::Unconditional(2).target(3, 10), 6);
+        assert_eq!(Jump::Reg0Eq(0).target(9, 10), 0);
+        // negative offsets count back from the next instruction
+        assert_eq!(Jump::Unconditional(-1).target(5, 10), 5);
+        assert_eq!(Jump::Reg1Lesser(-4).target(5, 10), 2);
+        // past the end wraps around
+        assert_eq!(Jump::Unconditional(5).target(8, 10), 4);
+        // before the start can not be fetched and restarts at 0
+        assert_eq!(Jump::Unconditional(-8).target(2, 10), 0);
+        // offsets larger than the program
+        assert_eq!(Jump::Unconditional(127).target(0, 4), 0);
+        assert_eq!(Jump::Unconditional(126).target(2, 4), 1);
+        assert_eq!(Jump::Unconditional(-128).target(0, 4), 0);
+        assert_eq!(Jump::Unconditional(-128).target(3, 1), 0);
+    }
+    #[test]
+    fn jump_targets_match_the_interpreter() {
+        for len in 1..=12 {
+            for idx in 0..len {
+                for offset in i8::MIN..=i8::MAX {
+                    let jump = Jump::Unconditional(offset);
+                    assert_eq!(jump.target(idx, len), interpreted(jump, idx, len), "offset {offset} from {idx} of {len}");
+                }
+            }
+        }
+    }
+    #[test]
+    fn disassembly_lists_jumps_with_their_targets() {
+        let code = assemble("start: jl r0, start\n    jle r1, 1\n    jg r1, -3\n    nop").unwrap();
+        let listing = disassemble(&code);
+        let lines = listing.words.iter().map(|word| word.response.to_string()).collect::<Vec<_>>();
+        assert_eq!(lines, ["jg r1, -1", "jle r1, 1", "jg r1, -3", "nop"]);
+        assert_eq!(Jump::Reg0Lesser(2).to_string(), "jle r0, 2");
+        assert_eq!(listing.words.iter().map(|word| word.jump_target).collect::<Vec<_>>(), [Some(0), Some(3), Some(0), None]);
+    }
+    #[test]
+    fn every_event_encodes_back_to_itself() {
+        for half in 0..=u16::MAX {
+            let event = Event::from(half);
+            let encoded = event.encode().unwrap_or_else(|err| panic!("{half:#06x} decodes to {event:?} which does not encode: {err}"));
+            assert_eq!(Event::from(encoded), event, "{half:#06x}");
+            assert_eq!(Event::from(encoded).encode(), Ok(encoded));
+        }
+    }
+    #[test]
+    fn every_response_encodes_back_to_itself() {
+        for half in 0..=u16::MAX {
+            let response = Response::from(half);
+            let encoded = response.encode().unwrap_or_else(|err| panic!("{half:#06x} decodes to {response:?} which does not encode: {err}"));
+            assert_eq!(Response::from(encoded), response, "{half:#06x}");
+            assert_eq!(Response::from(encoded).encode(), Ok(encoded));
+        }
+    }
+    #[test]
+    fn words_encode_back_to_themselves() {
+        for half in 0..=u16::MAX {
+            let word = (half as u32) << 16|half.rotate_left(7) as u32;
+            let decoded = EventResponse::from_word(word);
+            assert_eq!(EventResponse::from_word(decoded.to_word().unwrap()), decoded, "{word:#010x}");
+        }
+    }
+    #[test]
+    fn unencodable_values_are_reported() {
+        let call = Response::Call(RegConst::Constant(3));
+        assert_eq!(call.encode(), Err(EncodeError::Response(call)));
+        let call = Response::Call(RegConst::Register(Register::ByteRegister0_1));
+        assert_eq!(call.encode(), Err(EncodeError::Response(call)));
+        let step = Response::Move(RegConst::Register(Register::ByteRegister1_2));
+        assert_eq!(step.encode(), Err(EncodeError::Response(step)));
+        let add = Response::BinaryOp(BinaryOp::Add(Register::LongRegister0, RegConst::Register(Register::ByteRegister0_0)));
+        assert_eq!(add.encode(), Err(EncodeError::RegisterPair(Register::LongRegister0, Register::ByteRegister0_0)));
+        let xchg = Response::BinaryOp(BinaryOp::Xchg(Register::ByteRegister1_3, Register::LongRegister1));
+        assert_eq!(xchg.encode(), Err(EncodeError::RegisterPair(Register::ByteRegister1_3, Register::LongRegister1)));
+        let same = Response::BinaryOp(BinaryOp::Sub(Register::LongRegister1, RegConst::Register(Register::LongRegister1)));
+        assert_eq!(same.encode(), Err(EncodeError::RegisterPair(Register::LongRegister1, Register::LongRegister1)));
+        let compare = Event::Lesser(Register::ByteRegister0_2, Register::LongRegister0);
+        assert_eq!(compare.encode(), Err(EncodeError::RegisterPair(Register::ByteRegister0_2, Register::LongRegister0)));
+        let add = Response::BinaryOp(BinaryOp::Add(Register::LongRegister0, RegConst::Constant(1)));
+        assert_eq!(add.encode(), Err(EncodeError::Response(add)));
+        assert_eq!(Event::SurroundingSquaresEqual(RegConst::Constant(1), NEIGHBORHOOD_SELECTORS).encode(), Err(EncodeError::Neighborhood(NEIGHBORHOOD_SELECTORS)));
+        assert_eq!(Event::LayerGreater(RegConst::Constant(1), LAYER_SELECTORS).encode(), Err(EncodeError::Layer(LAYER_SELECTORS)));
+        let signal = Event::SignalEqual(RegConst::Constant(DIRECTIONAL_SIGNAL_CONSTANTS), Some(Direction::Top));
+        assert_eq!(signal.encode(), Err(EncodeError::SignalConstant(DIRECTIONAL_SIGNAL_CONSTANTS)));
+        let word = EventResponse { event: Event::Unconditional, response: Response::Call(RegConst::Constant(0)) };
+        assert_eq!(word.to_word(), Err(EncodeError::Response(Response::Call(RegConst::Constant(0)))));
+    }
+}
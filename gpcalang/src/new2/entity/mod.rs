@@ -1,560 +1,744 @@
-use std::{cell::{RefCell, UnsafeCell}, fmt::Debug, ops::{Add, AddAssign}, rc::Rc, sync::Arc};
-
-use bytecode::{Event, Jump, RegConst, Register, Response};
-
-use super::world::World;
-
-mod bytecode;
-
-#[repr(u8)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub enum Direction {
-    Right,
-    TopRight,
-    Top,
-    TopLeft,
-    Left,
-    BottomLeft,
-    Bottom,
-    BottomRight,
-}
-impl Direction {
-    pub fn perform_direction(&self, dir: &mut [u32; 2], width: u32, height: u32) {
-        match self {
-            Direction::Right => { 
-                dir[0] = dir[0].add(1).clamp(0, width);
-            }
-            Direction::TopRight => { 
-                dir[0] = dir[0].add(1).clamp(0, width);
-                dir[1] = dir[1].add(1).clamp(0, height); 
-            }
-            Direction::Top => { 
-                dir[1] = dir[1].add(1).clamp(0, height); 
-            }
-            Direction::TopLeft => { 
-                dir[0] = if dir[0] == 0 { dir[0] } else { dir[0]-1 };
-                dir[1] = dir[1].add(1).clamp(0, height); 
-            }
-            Direction::Left => { 
-                dir[0] = if dir[0] == 0 { dir[0] } else { dir[0]-1 };
-            }
-            Direction::BottomLeft => { 
-                dir[0] = if dir[0] == 0 { dir[0] } else { dir[0]-1 };
-                dir[1] = if dir[1] == 0 { dir[1] } else { dir[1]-1 }; 
-            }
-            Direction::Bottom => { 
-                dir[1] = if dir[1] == 0 { dir[1] } else { dir[1]-1 }; 
-            }
-            Direction::BottomRight => { 
-                dir[0] = dir[0].add(1).clamp(0, width);
-                dir[1] = if dir[1] == 0 { dir[1] } else { dir[1]-1 }; 
-            }
-        }
-    }
-}
-impl From<u64> for Direction {
-    fn from(value: u64) -> Self {
-        match value%8 {
-            0 => Direction::Right,
-            1 => Direction::TopRight,
-            2 => Direction::Top,
-            3 => Direction::TopLeft,
-            4 => Direction::Left,
-            5 => Direction::BottomLeft,
-            6 => Direction::Bottom,
-            7 => Direction::BottomRight,
-            _ => unreachable!()
-        }
-    }
-}
-#[derive(Clone, Copy)]
-union DataRegister {
-    long: u64,
-    byte: [u8; 4],
-}
-impl Debug for DataRegister {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        unsafe {
-            f.debug_tuple("Registers")
-            .field(&self.byte[0])
-            .field(&self.byte[1])
-            .field(&self.byte[2])
-            .field(&self.byte[3])
-            .finish()
-        }
-    }
-}
-impl Default for DataRegister {
-    fn default() -> Self {
-        unsafe { Self { long: 0 } }
-    }
-}
-impl DataRegister {
-    pub fn set_byte_register(&mut self, val: u8, idx: usize) {
-        unsafe {
-            self.byte[idx] = val;
-        }
-    }
-    pub fn set_long_register(&mut self, val: u64) {
-        unsafe {
-            self.long = val;
-        }
-    }
-    pub fn add_byte_register(&mut self, val: u8, idx: usize) {
-        unsafe {
-            self.byte[idx] += val;
-        }
-    }
-    pub fn sub_byte_register(&mut self, val: u8, idx: usize) {
-        unsafe {
-            self.byte[idx] -= val;
-        }
-    }
-    pub fn add_long_register(&mut self, val: u64) {
-        unsafe {
-            self.long += val;
-        }
-    }
-    pub fn sub_long_register(&mut self, val: u64) {
-        unsafe {
-            self.long -= val;
-        }
-    }
-    pub fn eq_long_register(&self, val: u64) -> bool {
-        unsafe {
-            self.long == val
-        }
-    }
-    pub fn neq_long_register(&self, val: u64) -> bool {
-        unsafe {
-            self.long != val
-        }
-    }
-    pub fn greater_long_register(&self, val: u64) -> bool {
-        unsafe {
-            self.long > val
-        }
-    }
-    pub fn lesser_long_register(&self, val: u64) -> bool {
-        unsafe {
-            self.long < val
-        }
-    }
-    pub fn greater_eq_long_register(&self, val: u64) -> bool {
-        unsafe {
-            self.long >= val
-        }
-    }
-    pub fn lesser_eq_long_register(&self, val: u64) -> bool {
-        unsafe {
-            self.long <= val
-        }
-    }
-}
-#[repr(C)]
-#[derive(Debug, Clone, Copy)]
-pub struct GPCAEntityInternal {
-    registers: [DataRegister; 2],
-    pub(crate) pos: [u32; 2],
-    pub(crate) id: u32,
-    energy: u32,
-    rip: usize,
-}
-
-impl GPCAEntityInternal {
-    pub fn new(x: u32, y: u32, id: u32, reg0: u64, reg1: u64, energy: u32) -> Self {
-        Self { registers: [DataRegister { long: reg0 }, DataRegister { long: reg1 }], pos: [x, y], id, energy, rip: 0 }
-    }
-    pub fn get(&self, register: Register) -> u64 {
-        unsafe {
-            match register {
-                Register::ByteRegister0_0 => self.registers[0].byte[0] as u64,
-                Register::ByteRegister0_1 => self.registers[0].byte[1] as u64,
-                Register::ByteRegister0_2 => self.registers[0].byte[2] as u64,
-                Register::ByteRegister0_3 => self.registers[0].byte[3] as u64,
-                Register::ByteRegister1_0 => self.registers[1].byte[0] as u64,
-                Register::ByteRegister1_1 => self.registers[1].byte[1] as u64,
-                Register::ByteRegister1_2 => self.registers[1].byte[2] as u64,
-                Register::ByteRegister1_3 => self.registers[1].byte[3] as u64,
-                Register::LongRegister0 => self.registers[0].long,
-                Register::LongRegister1 => self.registers[1].long,
-            }
-        }
-    }
-    pub fn get_const(&self, register: RegConst) -> u64 {
-        unsafe {
-            match register {
-                RegConst::Constant(constant) => constant as u64,
-                RegConst::Register(reg) => self.get(reg),
-            }
-        }
-    }
-    pub fn set_register(&mut self, register: Register, val: u64) {
-        unsafe {
-            match register {
-                Register::ByteRegister0_0 => self.registers[0].byte[0] = val as u8,
-                Register::ByteRegister0_1 => self.registers[0].byte[1] = val as u8,
-                Register::ByteRegister0_2 => self.registers[0].byte[2] = val as u8,
-                Register::ByteRegister0_3 => self.registers[0].byte[3] = val as u8,
-                Register::ByteRegister1_0 => self.registers[1].byte[0] = val as u8,
-                Register::ByteRegister1_1 => self.registers[1].byte[1] = val as u8,
-                Register::ByteRegister1_2 => self.registers[1].byte[2] = val as u8,
-                Register::ByteRegister1_3 => self.registers[1].byte[3] = val as u8,
-                Register::LongRegister0 => self.registers[0].long = val,
-                Register::LongRegister1 => self.registers[1].long = val,
-            }
-        }
-    }
-    pub fn handle_event(&self, event: Event, world: &World) -> bool {
-        // let world = world.borrow();
-        match event {
-            Event::Equal(lhs, rhs) => {
-                let (lhs, rhs) = (self.get(lhs), self.get(rhs));
-                lhs == rhs
-            }
-            Event::NotEqual(lhs, rhs) => {
-                let (lhs, rhs) = (self.get(lhs), self.get(rhs));
-                lhs != rhs
-            }
-            Event::Greater(lhs, rhs) => {
-                let (lhs, rhs) = (self.get(lhs), self.get(rhs));
-                lhs > rhs
-            }
-            Event::Lesser(lhs, rhs) => {
-                let (lhs, rhs) = (self.get(lhs), self.get(rhs));
-                lhs < rhs
-            }
-            Event::GreaterEqual(lhs, rhs) => {
-                let (lhs, rhs) = (self.get(lhs), self.get(rhs));
-                lhs >= rhs
-            }
-            Event::LesserEqual(lhs, rhs) => {
-                let (lhs, rhs) = (self.get(lhs), self.get(rhs));
-                lhs <= rhs
-            }
-            Event::SurroundingSquaresEqual(lhs) => {
-                let lhs = self.get_const(lhs);
-                lhs == world.surrounding_square_count(self.pos[0], self.pos[1]) as u64
-            }
-            Event::SurroundingSquaresNotEqual(lhs) => {
-                let lhs = self.get_const(lhs);
-                lhs != world.surrounding_square_count(self.pos[0], self.pos[1]) as u64
-            }
-            Event::SurroundingSquaresGreater(lhs) => {
-                let lhs = self.get_const(lhs);
-                lhs > world.surrounding_square_count(self.pos[0], self.pos[1]) as u64
-            }
-            Event::SurroundingSquaresLesser(lhs) => {
-                let lhs = self.get_const(lhs);
-                lhs < world.surrounding_square_count(self.pos[0], self.pos[1]) as u64
-            }
-            Event::SurroundingSquaresGreaterEqual(lhs) => {
-                let lhs = self.get_const(lhs);
-                lhs >= world.surrounding_square_count(self.pos[0], self.pos[1]) as u64
-            }
-            Event::SurroundingSquaresLesserEqual(lhs) => {
-                let lhs = self.get_const(lhs);
-                lhs <= world.surrounding_square_count(self.pos[0], self.pos[1]) as u64
-            }
-            Event::Unconditional => {
-                true
-            }
-        }
-    }
-    pub fn x(&self) -> u32 {
-        self.pos[0]
-    }
-    pub fn y(&self) -> u32 {
-        self.pos[1]
-    }
-    pub fn decrement_energy(&mut self) {
-        self.energy -= 1;
-    }
-    pub fn set_energy(&mut self, energy: u32) {
-        self.energy = energy;
-    }
-    pub fn get_energy(&self) -> u32 {
-        self.energy
-    }
-}
-
-pub struct GPCAEntity {
-    internal: UnsafeCell<GPCAEntityInternal>,
-    pub color: u32,
-    pub code: Vec<u32>,
-}
-#[derive(Debug, Clone, Copy)]
-pub struct EventResponse {
-    pub event: Event,
-    pub response: Response,
-}
-impl GPCAEntity {
-    pub fn new(x: u32, y: u32, id: u32, reg0: u64, reg1: u64, energy: u32, color: u32, code: Vec<u32>) -> Self {
-        Self { internal: UnsafeCell::new(GPCAEntityInternal::new(x, y, id, reg0, reg1, energy)), color, code }
-    }
-    pub fn parse(&self) -> Option<EventResponse> {
-        let code = self.code.get(self.inner().rip)?;
-        let event = ((code >> 16)&0xffff) as u16;
-        let response= (code&0xffff) as u16;
-        Some(EventResponse { event: Event::from(event), response: Response::from(response) })
-    }
-    /// [`clear`] when an entity is deleted or moved, this function will be used to
-    /// clear the current spot.
-    /// 
-    /// [`place`] when an entity is moved this function will be used to place the next
-    /// spot.
-    /// 
-    /// returns whether the entity should be deleted. if true then delete else continue.
-    pub fn step<F, H>(self: &Arc<Self>, 
-        world: &World, 
-        mut clear: F, 
-        mut place: H) -> bool
-        where F: FnMut(&GPCAEntity),
-            H: FnMut(&GPCAEntity) {
-        if !world.get(self.x(), self.y()) {
-            clear(self);
-            return true;
-        }
-        let event_response = self.next().unwrap_or_else(||{self.next().unwrap()});
-        if self.handle_event(event_response.event, &world) {
-            if event_response.response.is_move_step() {
-                clear(self);
-                self.handle_response(event_response.response, world);
-                place(self);
-            } else {
-                self.handle_response(event_response.response, world);
-            }
-        }
-        false
-    }
-    pub fn handle_event(&self, event: Event, world: &World) -> bool {
-        self.inner_mut().handle_event(event, world)
-    }
-    pub fn move_step(self: &Arc<Self>, step: Direction, world: &World) {
-        // let mut world = world.borrow_mut();
-        let prev = self.inner().pos;
-        step.perform_direction(&mut self.inner_mut().pos, world.width(), world.height());
-        
-        if world.get(self.inner().pos[0], self.inner().pos[1]) { // space is already occupied
-            self.inner_mut().pos = prev;
-        } else {
-            world.remove(prev[0], prev[1]);
-            world.set(&self);
-        }
-    }
-    pub fn handle_response(self: &Arc<Self>, response: Response, world: &World) {
-        match response {
-            Response::BinaryOp(bytecode::BinaryOp::Add(lhs, rhs)) => {
-                let lhs_val = self.inner().get(lhs);
-                let rhs_val = self.inner().get_const(rhs);
-                self.inner_mut().set_register(lhs, lhs_val.wrapping_add(rhs_val));
-            }
-            Response::BinaryOp(bytecode::BinaryOp::Sub(lhs, rhs)) => {
-                let lhs_val = self.inner().get(lhs);
-                let rhs_val = self.inner().get_const(rhs);
-                self.inner_mut().set_register(lhs, lhs_val.wrapping_sub(rhs_val));
-            }
-            Response::BinaryOp(bytecode::BinaryOp::Mul(lhs, rhs)) => {
-                let lhs_val = self.inner().get(lhs);
-                let rhs_val = self.inner().get_const(rhs);
-                self.inner_mut().set_register(lhs, lhs_val.wrapping_mul(rhs_val));
-            }
-            Response::BinaryOp(bytecode::BinaryOp::Div(lhs, rhs)) => {
-                let lhs_val = self.inner().get(lhs);
-                let rhs_val = self.inner().get_const(rhs);
-                if rhs_val == 0 {
-                    self.inner_mut().set_register(lhs, u64::MAX);
-                } else {
-                    self.inner_mut().set_register(lhs, lhs_val.wrapping_sub(rhs_val));
-                }
-            }
-            Response::BinaryOp(bytecode::BinaryOp::And(lhs, rhs)) => {
-                let lhs_val = self.inner().get(lhs);
-                let rhs_val = self.inner().get_const(rhs);
-                self.inner_mut().set_register(lhs, lhs_val&rhs_val);
-            }
-            Response::BinaryOp(bytecode::BinaryOp::Xor(lhs, rhs)) => {
-                let lhs_val = self.inner().get(lhs);
-                let rhs_val = self.inner().get_const(rhs);
-                self.inner_mut().set_register(lhs, lhs_val^rhs_val);
-            }
-            Response::BinaryOp(bytecode::BinaryOp::Or(lhs, rhs)) => {
-                let lhs_val = self.inner().get(lhs);
-                let rhs_val = self.inner().get_const(rhs);
-                self.inner_mut().set_register(lhs, lhs_val|rhs_val);
-            }
-            Response::BinaryOp(bytecode::BinaryOp::MoveAdd(lhs, rhs)) => {
-                let lhs_val = self.inner().get(lhs);
-                let rhs_val = self.inner().get_const(rhs);
-                let val = lhs_val.wrapping_add(rhs_val);
-                let step = Direction::from(val);
-                self.move_step(step, &world);
-            }
-            Response::BinaryOp(bytecode::BinaryOp::MoveSub(lhs, rhs)) => {
-                let lhs_val = self.inner().get(lhs);
-                let rhs_val = self.inner().get_const(rhs);
-                let val = lhs_val.wrapping_sub(rhs_val);
-                let step = Direction::from(val);
-                self.move_step(step, &world);
-            }
-            Response::BinaryOp(bytecode::BinaryOp::MoveMul(lhs, rhs)) => {
-                let lhs_val = self.inner().get(lhs);
-                let rhs_val = self.inner().get_const(rhs);
-                let val = lhs_val.wrapping_mul(rhs_val);
-                let step = Direction::from(val);
-                self.move_step(step, &world);
-            }
-            Response::BinaryOp(bytecode::BinaryOp::MoveDiv(lhs, rhs)) => {
-                let lhs_val = self.inner().get(lhs);
-                let rhs_val = self.inner().get_const(rhs);
-                let val = if rhs_val == 0 {
-                    u64::MAX
-                } else {
-                    lhs_val.wrapping_sub(rhs_val)
-                };
-                let step = Direction::from(val);
-                self.move_step(step, &world);
-            }
-            Response::BinaryOp(bytecode::BinaryOp::MoveAnd(lhs, rhs)) => {
-                let lhs_val = self.inner().get(lhs);
-                let rhs_val = self.inner().get_const(rhs);
-                let val = lhs_val&rhs_val;
-                let step = Direction::from(val);
-                self.move_step(step, &world);
-            }
-            Response::BinaryOp(bytecode::BinaryOp::MoveXor(lhs, rhs)) => {
-                let lhs_val = self.inner().get(lhs);
-                let rhs_val = self.inner().get_const(rhs);
-                let val = lhs_val^rhs_val;
-                let step = Direction::from(val);
-                self.move_step(step, &world);
-            }
-            Response::BinaryOp(bytecode::BinaryOp::MoveOr(lhs, rhs)) => {
-                let lhs_val = self.inner().get(lhs);
-                let rhs_val = self.inner().get_const(rhs);
-                let val = lhs_val|rhs_val;
-                let step = Direction::from(val);
-                self.move_step(step, &world);
-            }
-            Response::BinaryOp(bytecode::BinaryOp::Mov(lhs, rhs)) => {
-                let rhs_val = self.inner().get_const(rhs);
-                self.inner_mut().set_register(lhs, rhs_val);
-            }
-            Response::BinaryOp(bytecode::BinaryOp::Xchg(lhs, rhs)) => {
-                let lhs_val = self.inner().get(lhs);
-                let rhs_val = self.inner().get(rhs);
-                self.inner_mut().set_register(lhs, rhs_val);
-                self.inner_mut().set_register(rhs, lhs_val);
-            }
-            Response::Call(reg) => {
-                let get = self.inner().get_const(reg) as usize;
-                
-                if !world.functions.is_empty() {
-                    world.functions[get%world.functions.len()](&self, world);
-                }
-            }
-            Response::Move(reg) => {
-                let get = self.inner().get_const(reg);
-                let step = Direction::from(get);
-                self.move_step(step, &world);
-            }
-            Response::Jmp(reg) => {
-                let reg0 = unsafe { self.inner().registers[0].long };
-                let reg1 = unsafe { self.inner().registers[1].long };
-                let (should_jump) = match reg {
-                    bytecode::Jump::Reg0Eq(_) => {
-                        reg0 == reg1
-                    }
-                    bytecode::Jump::Reg0Greater(_) => {
-                        reg0 > reg1
-                    }
-                    bytecode::Jump::Reg0GreaterEq(_) => {
-                        reg0 >= reg1
-                    }
-                    bytecode::Jump::Reg0Lesser(_) => {
-                        reg0 <= reg1
-                    }
-                    bytecode::Jump::Reg0LesserEq(_) => {
-                        reg0 <= reg1
-                    }
-                    bytecode::Jump::Reg0Neq(_) => {
-                        reg0 != reg1
-                    }
-                    bytecode::Jump::Reg1Eq(_) => {
-                        reg1 == reg0
-                    }
-                    bytecode::Jump::Reg1Greater(_) => {
-                        reg1 > reg0
-                    }
-                    bytecode::Jump::Reg1GreaterEq(_) => {
-                        reg1 >= reg0
-                    }
-                    bytecode::Jump::Reg1Lesser(_) => {
-                        reg1 <= reg0
-                    }
-                    bytecode::Jump::Reg1LesserEq(_) => {
-                        reg1 <= reg0
-                    }
-                    bytecode::Jump::Reg1Neq(_) => {
-                        reg1 != reg0
-                    }
-                    bytecode::Jump::Unconditional(_) => {
-                        true
-                    }
-                };
-                if should_jump {
-                    let (bytecode::Jump::Reg0Eq(jmp)    | bytecode::Jump::Reg0Greater(jmp)  | 
-                    bytecode::Jump::Reg0GreaterEq(jmp)  | bytecode::Jump::Reg0Lesser(jmp)   | 
-                    bytecode::Jump::Reg0LesserEq(jmp)   | bytecode::Jump::Reg0Neq(jmp)      | 
-                    bytecode::Jump::Unconditional(jmp) | bytecode::Jump::Reg1Eq(jmp)    | bytecode::Jump::Reg1Greater(jmp)  | 
-                    bytecode::Jump::Reg1GreaterEq(jmp)  | bytecode::Jump::Reg1Lesser(jmp)   | 
-                    bytecode::Jump::Reg1LesserEq(jmp)   | bytecode::Jump::Reg1Neq(jmp)) = reg;
-                    let len = self.code.len() as isize;
-                    let rip = self.inner().rip as isize;
-                    let jmp_loc = ((jmp as isize + rip)%len) as usize;
-                    self.inner_mut().rip = jmp_loc;
-                }
-            }
-            Response::Nop => {}
-        }
-    }
-    pub fn x(&self) -> u32 {
-        self.inner().pos[0]
-    }
-    pub fn y(&self) -> u32 {
-        self.inner().pos[1]
-    }
-    pub fn next_rip(&self) {
-        if self.inner().rip < self.code.len() {
-            self.inner_mut().rip += 1;
-        } else {
-            self.inner_mut().rip = 0;
-        }
-    }
-    pub fn execute_next(self: Arc<Self>, world: &World) {
-        let next = self.next().unwrap_or_else(||{self.next().unwrap()});
-        if self.handle_event(next.event, &world) {
-            self.handle_response(next.response, world);
-        }
-    }
-    pub fn inner(&self) -> &GPCAEntityInternal {
-        unsafe { self.internal.get().as_ref().unwrap() }
-    }
-    pub fn inner_mut(&self) -> &mut GPCAEntityInternal {
-        unsafe { self.internal.get().as_mut().unwrap() }
-    }
-    pub fn decrement_energy(&self) {
-        self.inner_mut().decrement_energy();
-    }
-    pub fn set_energy(&self, energy: u32) {
-        self.inner_mut().set_energy(energy);
-    }
-    pub fn get_energy(&self) -> u32 {
-        self.inner().get_energy()
-    }
-    pub fn next(self: &Arc<Self>) -> Option<EventResponse> {
-        let next = self.parse();
-        self.next_rip();
-        next
-    }
-}
\ No newline at end of file
+use std::{fmt::Debug, ops::Add};
+
+use bytecode::{EncodeError, Event, RegConst, Register, Response};
+use serde::{Deserialize, Serialize};
+
+use super::world::{EntityHandle, World};
+
+pub mod bytecode;
+pub mod asm;
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Direction {
+    Right,
+    TopRight,
+    Top,
+    TopLeft,
+    Left,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+impl Direction {
+    /// every direction in the order of their values.
+    pub const ALL: [Direction; 8] = [
+        Direction::Right, Direction::TopRight, Direction::Top, Direction::TopLeft,
+        Direction::Left, Direction::BottomLeft, Direction::Bottom, Direction::BottomRight,
+    ];
+    /// name of the direction in assembly, e.g. `top_right`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Direction::Right => "right",
+            Direction::TopRight => "top_right",
+            Direction::Top => "top",
+            Direction::TopLeft => "top_left",
+            Direction::Left => "left",
+            Direction::BottomLeft => "bottom_left",
+            Direction::Bottom => "bottom",
+            Direction::BottomRight => "bottom_right",
+        }
+    }
+    /// change in x and y when taking a step in this direction.
+    pub fn delta(&self) -> (i64, i64) {
+        match self {
+            Direction::Right => (1, 0),
+            Direction::TopRight => (1, 1),
+            Direction::Top => (0, 1),
+            Direction::TopLeft => (-1, 1),
+            Direction::Left => (-1, 0),
+            Direction::BottomLeft => (-1, -1),
+            Direction::Bottom => (0, -1),
+            Direction::BottomRight => (1, -1),
+        }
+    }
+    /// steps `dir` while clamping it to the map, see [`World::position_at_direction`]
+    /// for a step that honors the topology of the world.
+    pub fn perform_direction(&self, dir: &mut [u32; 2], width: u32, height: u32) {
+        let (width, height) = (width.saturating_sub(1), height.saturating_sub(1));
+        match self {
+            Direction::Right => { 
+                dir[0] = dir[0].add(1).clamp(0, width);
+            }
+            Direction::TopRight => { 
+                dir[0] = dir[0].add(1).clamp(0, width);
+                dir[1] = dir[1].add(1).clamp(0, height); 
+            }
+            Direction::Top => { 
+                dir[1] = dir[1].add(1).clamp(0, height); 
+            }
+            Direction::TopLeft => { 
+                dir[0] = if dir[0] == 0 { dir[0] } else { dir[0]-1 };
+                dir[1] = dir[1].add(1).clamp(0, height); 
+            }
+            Direction::Left => { 
+                dir[0] = if dir[0] == 0 { dir[0] } else { dir[0]-1 };
+            }
+            Direction::BottomLeft => { 
+                dir[0] = if dir[0] == 0 { dir[0] } else { dir[0]-1 };
+                dir[1] = if dir[1] == 0 { dir[1] } else { dir[1]-1 }; 
+            }
+            Direction::Bottom => { 
+                dir[1] = if dir[1] == 0 { dir[1] } else { dir[1]-1 }; 
+            }
+            Direction::BottomRight => { 
+                dir[0] = dir[0].add(1).clamp(0, width);
+                dir[1] = if dir[1] == 0 { dir[1] } else { dir[1]-1 }; 
+            }
+        }
+    }
+}
+impl From<u64> for Direction {
+    fn from(value: u64) -> Self {
+        match value%8 {
+            0 => Direction::Right,
+            1 => Direction::TopRight,
+            2 => Direction::Top,
+            3 => Direction::TopLeft,
+            4 => Direction::Left,
+            5 => Direction::BottomLeft,
+            6 => Direction::Bottom,
+            7 => Direction::BottomRight,
+            _ => unreachable!()
+        }
+    }
+}
+/// byte `idx` of a 64 bit register, counting from the least significant one.
+fn byte(register: u64, idx: u32) -> u64 {
+    (register >> (idx*8))&0xff
+}
+/// `register` with byte `idx` replaced by the low byte of `val`.
+fn with_byte(register: u64, idx: u32, val: u64) -> u64 {
+    (register&!(0xff << (idx*8)))|((val&0xff) << (idx*8))
+}
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct GPCAEntityInternal {
+    /// the byte registers are the four low bytes of these.
+    registers: [u64; 2],
+    pub(crate) pos: [u32; 2],
+    /// set once the entity is added to a world.
+    pub(crate) handle: EntityHandle,
+    energy: u32,
+    rip: usize,
+}
+
+impl GPCAEntityInternal {
+    pub fn new(x: u32, y: u32, reg0: u64, reg1: u64, energy: u32) -> Self {
+        Self { registers: [reg0, reg1], pos: [x, y], handle: EntityHandle::default(), energy, rip: 0 }
+    }
+    pub fn get(&self, register: Register) -> u64 {
+        match register {
+            Register::ByteRegister0_0 => byte(self.registers[0], 0),
+            Register::ByteRegister0_1 => byte(self.registers[0], 1),
+            Register::ByteRegister0_2 => byte(self.registers[0], 2),
+            Register::ByteRegister0_3 => byte(self.registers[0], 3),
+            Register::ByteRegister1_0 => byte(self.registers[1], 0),
+            Register::ByteRegister1_1 => byte(self.registers[1], 1),
+            Register::ByteRegister1_2 => byte(self.registers[1], 2),
+            Register::ByteRegister1_3 => byte(self.registers[1], 3),
+            Register::LongRegister0 => self.registers[0],
+            Register::LongRegister1 => self.registers[1],
+        }
+    }
+    pub fn get_const(&self, register: RegConst) -> u64 {
+        match register {
+            RegConst::Constant(constant) => constant as u64,
+            RegConst::Register(reg) => self.get(reg),
+        }
+    }
+    /// both 64 bit registers, the byte registers are views into them.
+    pub fn registers(&self) -> [u64; 2] {
+        self.registers
+    }
+    /// index of the next instruction, one past the end of the code wraps to the first
+    /// one when it is fetched.
+    pub fn rip(&self) -> usize {
+        self.rip
+    }
+    /// index of the instruction [`GPCAEntityInternal::fetch`] returns next from code
+    /// of `code_len` words.
+    pub fn next_index(&self, code_len: usize) -> usize {
+        if self.rip < code_len { self.rip } else { 0 }
+    }
+    pub fn set_register(&mut self, register: Register, val: u64) {
+        match register {
+            Register::ByteRegister0_0 => self.registers[0] = with_byte(self.registers[0], 0, val),
+            Register::ByteRegister0_1 => self.registers[0] = with_byte(self.registers[0], 1, val),
+            Register::ByteRegister0_2 => self.registers[0] = with_byte(self.registers[0], 2, val),
+            Register::ByteRegister0_3 => self.registers[0] = with_byte(self.registers[0], 3, val),
+            Register::ByteRegister1_0 => self.registers[1] = with_byte(self.registers[1], 0, val),
+            Register::ByteRegister1_1 => self.registers[1] = with_byte(self.registers[1], 1, val),
+            Register::ByteRegister1_2 => self.registers[1] = with_byte(self.registers[1], 2, val),
+            Register::ByteRegister1_3 => self.registers[1] = with_byte(self.registers[1], 3, val),
+            Register::LongRegister0 => self.registers[0] = val,
+            Register::LongRegister1 => self.registers[1] = val,
+        }
+    }
+    pub fn handle_event(&self, event: Event, world: &World) -> bool {
+        // let world = world.borrow();
+        match event {
+            Event::Equal(lhs, rhs) => {
+                let (lhs, rhs) = (self.get(lhs), self.get(rhs));
+                lhs == rhs
+            }
+            Event::NotEqual(lhs, rhs) => {
+                let (lhs, rhs) = (self.get(lhs), self.get(rhs));
+                lhs != rhs
+            }
+            Event::Greater(lhs, rhs) => {
+                let (lhs, rhs) = (self.get(lhs), self.get(rhs));
+                lhs > rhs
+            }
+            Event::Lesser(lhs, rhs) => {
+                let (lhs, rhs) = (self.get(lhs), self.get(rhs));
+                lhs < rhs
+            }
+            Event::GreaterEqual(lhs, rhs) => {
+                let (lhs, rhs) = (self.get(lhs), self.get(rhs));
+                lhs >= rhs
+            }
+            Event::LesserEqual(lhs, rhs) => {
+                let (lhs, rhs) = (self.get(lhs), self.get(rhs));
+                lhs <= rhs
+            }
+            Event::SurroundingSquaresEqual(lhs, neighborhood) => {
+                let lhs = self.get_const(lhs);
+                lhs == world.neighbor_count(self.pos, neighborhood) as u64
+            }
+            Event::SurroundingSquaresNotEqual(lhs, neighborhood) => {
+                let lhs = self.get_const(lhs);
+                lhs != world.neighbor_count(self.pos, neighborhood) as u64
+            }
+            Event::SurroundingSquaresGreater(lhs, neighborhood) => {
+                let lhs = self.get_const(lhs);
+                lhs > world.neighbor_count(self.pos, neighborhood) as u64
+            }
+            Event::SurroundingSquaresLesser(lhs, neighborhood) => {
+                let lhs = self.get_const(lhs);
+                lhs < world.neighbor_count(self.pos, neighborhood) as u64
+            }
+            Event::SurroundingSquaresGreaterEqual(lhs, neighborhood) => {
+                let lhs = self.get_const(lhs);
+                lhs >= world.neighbor_count(self.pos, neighborhood) as u64
+            }
+            Event::SurroundingSquaresLesserEqual(lhs, neighborhood) => {
+                let lhs = self.get_const(lhs);
+                lhs <= world.neighbor_count(self.pos, neighborhood) as u64
+            }
+            Event::LayerEqual(lhs, layer) => {
+                let lhs = self.get_const(lhs);
+                lhs == self.layer_value(layer, world)
+            }
+            Event::LayerNotEqual(lhs, layer) => {
+                let lhs = self.get_const(lhs);
+                lhs != self.layer_value(layer, world)
+            }
+            Event::LayerGreater(lhs, layer) => {
+                let lhs = self.get_const(lhs);
+                lhs > self.layer_value(layer, world)
+            }
+            Event::LayerLesser(lhs, layer) => {
+                let lhs = self.get_const(lhs);
+                lhs < self.layer_value(layer, world)
+            }
+            Event::LayerGreaterEqual(lhs, layer) => {
+                let lhs = self.get_const(lhs);
+                lhs >= self.layer_value(layer, world)
+            }
+            Event::LayerLesserEqual(lhs, layer) => {
+                let lhs = self.get_const(lhs);
+                lhs <= self.layer_value(layer, world)
+            }
+            Event::SignalEqual(lhs, direction) => {
+                let lhs = self.get_const(lhs);
+                lhs == self.signal(direction, world)
+            }
+            Event::SignalNotEqual(lhs, direction) => {
+                let lhs = self.get_const(lhs);
+                lhs != self.signal(direction, world)
+            }
+            Event::SignalGreater(lhs, direction) => {
+                let lhs = self.get_const(lhs);
+                lhs > self.signal(direction, world)
+            }
+            Event::SignalLesser(lhs, direction) => {
+                let lhs = self.get_const(lhs);
+                lhs < self.signal(direction, world)
+            }
+            Event::SignalGreaterEqual(lhs, direction) => {
+                let lhs = self.get_const(lhs);
+                lhs >= self.signal(direction, world)
+            }
+            Event::SignalLesserEqual(lhs, direction) => {
+                let lhs = self.get_const(lhs);
+                lhs <= self.signal(direction, world)
+            }
+            Event::Unconditional => {
+                true
+            }
+        }
+    }
+    /// applies the parts of `response` that only concern the entity itself and
+    /// returns what is left for the world to do. `code_len` is the length of the code
+    /// jumps wrap around in.
+    pub fn handle_response(&mut self, response: Response, code_len: usize) -> Effect {
+        match response {
+            Response::BinaryOp(bytecode::BinaryOp::Add(lhs, rhs)) => {
+                let lhs_val = self.get(lhs);
+                let rhs_val = self.get_const(rhs);
+                self.set_register(lhs, lhs_val.wrapping_add(rhs_val));
+                Effect::None
+            }
+            Response::BinaryOp(bytecode::BinaryOp::Sub(lhs, rhs)) => {
+                let lhs_val = self.get(lhs);
+                let rhs_val = self.get_const(rhs);
+                self.set_register(lhs, lhs_val.wrapping_sub(rhs_val));
+                Effect::None
+            }
+            Response::BinaryOp(bytecode::BinaryOp::Mul(lhs, rhs)) => {
+                let lhs_val = self.get(lhs);
+                let rhs_val = self.get_const(rhs);
+                self.set_register(lhs, lhs_val.wrapping_mul(rhs_val));
+                Effect::None
+            }
+            Response::BinaryOp(bytecode::BinaryOp::Div(lhs, rhs)) => {
+                let lhs_val = self.get(lhs);
+                let rhs_val = self.get_const(rhs);
+                if rhs_val == 0 {
+                    self.set_register(lhs, u64::MAX);
+                } else {
+                    self.set_register(lhs, lhs_val.wrapping_sub(rhs_val));
+                }
+                Effect::None
+            }
+            Response::BinaryOp(bytecode::BinaryOp::And(lhs, rhs)) => {
+                let lhs_val = self.get(lhs);
+                let rhs_val = self.get_const(rhs);
+                self.set_register(lhs, lhs_val&rhs_val);
+                Effect::None
+            }
+            Response::BinaryOp(bytecode::BinaryOp::Xor(lhs, rhs)) => {
+                let lhs_val = self.get(lhs);
+                let rhs_val = self.get_const(rhs);
+                self.set_register(lhs, lhs_val^rhs_val);
+                Effect::None
+            }
+            Response::BinaryOp(bytecode::BinaryOp::Or(lhs, rhs)) => {
+                let lhs_val = self.get(lhs);
+                let rhs_val = self.get_const(rhs);
+                self.set_register(lhs, lhs_val|rhs_val);
+                Effect::None
+            }
+            Response::BinaryOp(bytecode::BinaryOp::MoveAdd(lhs, rhs)) => {
+                let lhs_val = self.get(lhs);
+                let rhs_val = self.get_const(rhs);
+                let val = lhs_val.wrapping_add(rhs_val);
+                let step = Direction::from(val);
+                Effect::Move(step)
+            }
+            Response::BinaryOp(bytecode::BinaryOp::MoveSub(lhs, rhs)) => {
+                let lhs_val = self.get(lhs);
+                let rhs_val = self.get_const(rhs);
+                let val = lhs_val.wrapping_sub(rhs_val);
+                let step = Direction::from(val);
+                Effect::Move(step)
+            }
+            Response::BinaryOp(bytecode::BinaryOp::MoveMul(lhs, rhs)) => {
+                let lhs_val = self.get(lhs);
+                let rhs_val = self.get_const(rhs);
+                let val = lhs_val.wrapping_mul(rhs_val);
+                let step = Direction::from(val);
+                Effect::Move(step)
+            }
+            Response::BinaryOp(bytecode::BinaryOp::MoveDiv(lhs, rhs)) => {
+                let lhs_val = self.get(lhs);
+                let rhs_val = self.get_const(rhs);
+                let val = if rhs_val == 0 {
+                    u64::MAX
+                } else {
+                    lhs_val.wrapping_sub(rhs_val)
+                };
+                let step = Direction::from(val);
+                Effect::Move(step)
+            }
+            Response::BinaryOp(bytecode::BinaryOp::MoveAnd(lhs, rhs)) => {
+                let lhs_val = self.get(lhs);
+                let rhs_val = self.get_const(rhs);
+                let val = lhs_val&rhs_val;
+                let step = Direction::from(val);
+                Effect::Move(step)
+            }
+            Response::BinaryOp(bytecode::BinaryOp::MoveXor(lhs, rhs)) => {
+                let lhs_val = self.get(lhs);
+                let rhs_val = self.get_const(rhs);
+                let val = lhs_val^rhs_val;
+                let step = Direction::from(val);
+                Effect::Move(step)
+            }
+            Response::BinaryOp(bytecode::BinaryOp::MoveOr(lhs, rhs)) => {
+                let lhs_val = self.get(lhs);
+                let rhs_val = self.get_const(rhs);
+                let val = lhs_val|rhs_val;
+                let step = Direction::from(val);
+                Effect::Move(step)
+            }
+            Response::BinaryOp(bytecode::BinaryOp::Mov(lhs, rhs)) => {
+                let rhs_val = self.get_const(rhs);
+                self.set_register(lhs, rhs_val);
+                Effect::None
+            }
+            Response::BinaryOp(bytecode::BinaryOp::Xchg(lhs, rhs)) => {
+                let lhs_val = self.get(lhs);
+                let rhs_val = self.get(rhs);
+                self.set_register(lhs, rhs_val);
+                self.set_register(rhs, lhs_val);
+                Effect::None
+            }
+            Response::Call(reg) => {
+                Effect::Call(self.get_const(reg) as usize)
+            }
+            Response::Harvest(selector) => {
+                Effect::Harvest(selector)
+            }
+            Response::Signal(reg) => {
+                // values that do not fit in a cell deposit as much as a cell can hold
+                Effect::Signal(u32::try_from(self.get(reg)).unwrap_or(u32::MAX))
+            }
+            Response::Move(reg) => {
+                let get = self.get_const(reg);
+                let step = Direction::from(get);
+                Effect::Move(step)
+            }
+            Response::Jmp(reg) => {
+                let reg0 = self.registers[0];
+                let reg1 = self.registers[1];
+                let should_jump = match reg {
+                    bytecode::Jump::Reg0Eq(_) => {
+                        reg0 == reg1
+                    }
+                    bytecode::Jump::Reg0Greater(_) => {
+                        reg0 > reg1
+                    }
+                    bytecode::Jump::Reg0GreaterEq(_) => {
+                        reg0 >= reg1
+                    }
+                    // compares like `Reg0LesserEq`, evolved code depends on it
+                    bytecode::Jump::Reg0Lesser(_) => {
+                        reg0 <= reg1
+                    }
+                    bytecode::Jump::Reg0LesserEq(_) => {
+                        reg0 <= reg1
+                    }
+                    bytecode::Jump::Reg0Neq(_) => {
+                        reg0 != reg1
+                    }
+                    bytecode::Jump::Reg1Eq(_) => {
+                        reg1 == reg0
+                    }
+                    bytecode::Jump::Reg1Greater(_) => {
+                        reg1 > reg0
+                    }
+                    bytecode::Jump::Reg1GreaterEq(_) => {
+                        reg1 >= reg0
+                    }
+                    // compares like `Reg1LesserEq`
+                    bytecode::Jump::Reg1Lesser(_) => {
+                        reg1 <= reg0
+                    }
+                    bytecode::Jump::Reg1LesserEq(_) => {
+                        reg1 <= reg0
+                    }
+                    bytecode::Jump::Reg1Neq(_) => {
+                        reg1 != reg0
+                    }
+                    bytecode::Jump::Unconditional(_) => {
+                        true
+                    }
+                };
+                if should_jump {
+                    let (bytecode::Jump::Reg0Eq(jmp)    | bytecode::Jump::Reg0Greater(jmp)  | 
+                    bytecode::Jump::Reg0GreaterEq(jmp)  | bytecode::Jump::Reg0Lesser(jmp)   | 
+                    bytecode::Jump::Reg0LesserEq(jmp)   | bytecode::Jump::Reg0Neq(jmp)      | 
+                    bytecode::Jump::Unconditional(jmp) | bytecode::Jump::Reg1Eq(jmp)    | bytecode::Jump::Reg1Greater(jmp)  | 
+                    bytecode::Jump::Reg1GreaterEq(jmp)  | bytecode::Jump::Reg1Lesser(jmp)   | 
+                    bytecode::Jump::Reg1LesserEq(jmp)   | bytecode::Jump::Reg1Neq(jmp)) = reg;
+                    let len = code_len as isize;
+                    let rip = self.rip as isize;
+                    let jmp_loc = ((jmp as isize + rip)%len) as usize;
+                    self.rip = jmp_loc;
+                }
+                Effect::None
+            }
+            Response::Nop => Effect::None,
+        }
+    }
+    /// the instruction at `rip` of the decoded `code` and advances `rip`, see
+    /// [`GPCAEntity::fetch`].
+    pub fn fetch(&mut self, code: &[EventResponse]) -> EventResponse {
+        let mut next = || {
+            let instruction = code.get(self.rip).copied();
+            self.rip = if self.rip < code.len() { self.rip+1 } else { 0 };
+            instruction
+        };
+        next().unwrap_or_else(|| next().unwrap())
+    }
+    /// value of the selected layer at the current cell, 0 if the world has no layers.
+    fn layer_value(&self, selector: u8, world: &World) -> u64 {
+        world.select_layer(selector).map(|layer| world.layer_value(layer, self.pos)).unwrap_or_default() as u64
+    }
+    /// signal at the current cell or in `direction` of it, 0 past an edge that does not
+    /// wrap.
+    fn signal(&self, direction: Option<Direction>, world: &World) -> u64 {
+        let pos = match direction {
+            Some(direction) => world.position_at_direction(self.pos, direction),
+            None => Some(self.pos),
+        };
+        pos.map(|pos| world.signal(pos)).unwrap_or_default() as u64
+    }
+    pub fn x(&self) -> u32 {
+        self.pos[0]
+    }
+    pub fn y(&self) -> u32 {
+        self.pos[1]
+    }
+    pub fn pos(&self) -> [u32; 2] {
+        self.pos
+    }
+    pub fn decrement_energy(&mut self) {
+        self.energy -= 1;
+    }
+    pub fn set_energy(&mut self, energy: u32) {
+        self.energy = energy;
+    }
+    /// takes `cost` from the energy, at most all of it.
+    pub fn spend_energy(&mut self, cost: u32) {
+        self.energy = self.energy.saturating_sub(cost);
+    }
+    pub fn get_energy(&self) -> u32 {
+        self.energy
+    }
+}
+
+/// what is left of a response after [`GPCAEntity::handle_response`] updated the
+/// entity, the world carries it out since it changes more than the entity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    None,
+    /// step into the neighboring cell if it is free.
+    Move(Direction),
+    /// harvest the layer the selector picks at the current cell.
+    Harvest(u8),
+    /// deposit this much signal at the current cell.
+    Signal(u32),
+    /// call the user function at this index, wrapping around the function table.
+    Call(usize),
+}
+
+pub struct GPCAEntity {
+    internal: GPCAEntityInternal,
+    pub color: u32,
+    program: Program,
+}
+/// complete state of an entity as it is written into world snapshots.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct EntitySnapshot {
+    pub(crate) registers: [u64; 2],
+    pub(crate) pos: [u32; 2],
+    pub(crate) handle: EntityHandle,
+    pub(crate) energy: u32,
+    pub(crate) rip: u64,
+    pub(crate) color: u32,
+    pub(crate) code: Vec<u32>,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventResponse {
+    pub event: Event,
+    pub response: Response,
+}
+impl EventResponse {
+    /// decodes a single word of an entities code, the event lives in the upper 16 bits.
+    pub fn from_word(word: u32) -> Self {
+        let event = ((word >> 16)&0xffff) as u16;
+        let response = (word&0xffff) as u16;
+        Self { event: Event::from(event), response: Response::from(response) }
+    }
+    /// inverse of [`EventResponse::from_word`].
+    pub fn to_word(&self) -> Result<u32, EncodeError> {
+        Ok(((self.event.encode()? as u32) << 16)|self.response.encode()? as u32)
+    }
+}
+
+/// the code of an entity together with every word of it decoded, so the interpreter
+/// decodes a word once instead of every time it runs it. The decoded instructions are
+/// rebuilt whenever the code changes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Program {
+    code: Vec<u32>,
+    decoded: Vec<EventResponse>,
+}
+impl Program {
+    pub fn new(code: Vec<u32>) -> Self {
+        let decoded = code.iter().map(|word| EventResponse::from_word(*word)).collect();
+        Self { code, decoded }
+    }
+    pub fn code(&self) -> &[u32] {
+        &self.code
+    }
+    /// `decoded()[idx]` is `code()[idx]` decoded with [`EventResponse::from_word`].
+    pub fn decoded(&self) -> &[EventResponse] {
+        &self.decoded
+    }
+    pub fn len(&self) -> usize {
+        self.code.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty()
+    }
+    /// replaces the word at `idx` and decodes it again.
+    pub fn set_word(&mut self, idx: usize, word: u32) {
+        self.code[idx] = word;
+        self.decoded[idx] = EventResponse::from_word(word);
+    }
+}
+impl From<Vec<u32>> for Program {
+    fn from(code: Vec<u32>) -> Self {
+        Self::new(code)
+    }
+}
+
+impl GPCAEntity {
+    pub fn new(x: u32, y: u32, reg0: u64, reg1: u64, energy: u32, color: u32, code: Vec<u32>) -> Self {
+        Self { internal: GPCAEntityInternal::new(x, y, reg0, reg1, energy), color, program: Program::new(code) }
+    }
+    pub fn code(&self) -> &[u32] {
+        self.program.code()
+    }
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+    /// replaces the code, `rip` is left where it is.
+    pub fn set_code(&mut self, code: Vec<u32>) {
+        self.program = Program::new(code);
+    }
+    /// replaces the word of the code at `idx`.
+    pub fn set_word(&mut self, idx: usize, word: u32) {
+        self.program.set_word(idx, word);
+    }
+    pub fn parse(&self) -> Option<EventResponse> {
+        self.program.decoded().get(self.inner().rip).copied()
+    }
+    pub fn handle_event(&self, event: Event, world: &World) -> bool {
+        self.internal.handle_event(event, world)
+    }
+    /// applies the parts of `response` that only concern the entity itself and
+    /// returns what is left for the world to do.
+    pub fn handle_response(&mut self, response: Response) -> Effect {
+        self.internal.handle_response(response, self.program.len())
+    }
+    pub fn x(&self) -> u32 {
+        self.inner().pos[0]
+    }
+    pub fn y(&self) -> u32 {
+        self.inner().pos[1]
+    }
+    pub fn pos(&self) -> [u32; 2] {
+        self.inner().pos
+    }
+    /// the handle the world gave the entity, the default handle until it is added to
+    /// one.
+    pub fn handle(&self) -> EntityHandle {
+        self.internal.handle
+    }
+    pub fn next_rip(&mut self) {
+        if self.internal.rip < self.program.len() {
+            self.internal.rip += 1;
+        } else {
+            self.internal.rip = 0;
+        }
+    }
+    /// index of the instruction [`GPCAEntity::fetch`] returns next.
+    pub fn next_index(&self) -> usize {
+        self.internal.next_index(self.program.len())
+    }
+    /// the instruction at `rip`, past the end of the code execution wraps around to
+    /// the first instruction.
+    pub fn fetch(&mut self) -> EventResponse {
+        self.internal.fetch(self.program.decoded())
+    }
+    pub fn inner(&self) -> &GPCAEntityInternal {
+        &self.internal
+    }
+    pub fn inner_mut(&mut self) -> &mut GPCAEntityInternal {
+        &mut self.internal
+    }
+    pub fn decrement_energy(&mut self) {
+        self.internal.decrement_energy();
+    }
+    pub fn set_energy(&mut self, energy: u32) {
+        self.internal.set_energy(energy);
+    }
+    pub fn get_energy(&self) -> u32 {
+        self.internal.get_energy()
+    }
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<EventResponse> {
+        let next = self.parse();
+        self.next_rip();
+        next
+    }
+    pub(crate) fn snapshot(&self) -> EntitySnapshot {
+        let inner = self.inner();
+        EntitySnapshot { registers: inner.registers, pos: inner.pos, handle: inner.handle, energy: inner.energy, rip: inner.rip as u64, color: self.color, code: self.program.code().to_vec() }
+    }
+    pub(crate) fn from_snapshot(snapshot: EntitySnapshot) -> Self {
+        let mut this = Self::new(snapshot.pos[0], snapshot.pos[1], snapshot.registers[0], snapshot.registers[1], snapshot.energy, snapshot.color, snapshot.code);
+        this.internal.handle = snapshot.handle;
+        this.internal.rip = snapshot.rip as usize;
+        this
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytecode::Jump;
+
+    /// where a jump at the start of a long program lands with the registers set to
+    /// `reg0` and `reg1`.
+    fn jump(jump: Jump, reg0: u64, reg1: u64) -> usize {
+        let mut entity = GPCAEntityInternal::new(0, 0, reg0, reg1, 0);
+        entity.handle_response(Response::Jmp(jump), 100);
+        entity.rip()
+    }
+
+    #[test]
+    fn lesser_jumps_include_equal_registers() {
+        assert_eq!(jump(Jump::Reg0Lesser(5), 3, 3), 5);
+        assert_eq!(jump(Jump::Reg0Lesser(5), 2, 3), 5);
+        assert_eq!(jump(Jump::Reg0Lesser(5), 4, 3), 0);
+        assert_eq!(jump(Jump::Reg1Lesser(5), 3, 3), 5);
+        assert_eq!(jump(Jump::Reg1Lesser(5), 3, 2), 5);
+        assert_eq!(jump(Jump::Reg1Lesser(5), 3, 4), 0);
+    }
+    #[test]
+    fn greater_jumps_are_strict() {
+        assert_eq!(jump(Jump::Reg0Greater(5), 3, 3), 0);
+        assert_eq!(jump(Jump::Reg1Greater(5), 2, 3), 5);
+        assert_eq!(jump(Jump::Reg0LesserEq(5), 3, 3), 5);
+        assert_eq!(jump(Jump::Reg1GreaterEq(5), 3, 3), 5);
+    }
+    #[test]
+    fn programs_decode_the_words_they_are_given() {
+        let decoded = |program: &Program| program.code().iter().map(|word| EventResponse::from_word(*word)).collect::<Vec<_>>();
+        let mut program = Program::new(vec![0, 0x0001_0002, u32::MAX]);
+        assert_eq!(program.decoded(), decoded(&program));
+        program.set_word(1, 0xdead_beef);
+        assert_eq!(program.decoded()[1], EventResponse::from_word(0xdead_beef));
+        assert_eq!(program.decoded(), decoded(&program));
+        let mut entity = GPCAEntity::new(0, 0, 0, 0, 0, 0, vec![0; 4]);
+        entity.set_word(3, 0x1234_5678);
+        assert_eq!(entity.program().decoded(), decoded(entity.program()));
+        entity.set_code(vec![7, 8]);
+        assert_eq!(entity.program().decoded(), decoded(entity.program()));
+    }
+}
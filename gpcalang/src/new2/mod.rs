@@ -1,2 +1,2 @@
-pub mod entity;
+pub mod entity;
 pub mod world;
\ No newline at end of file
@@ -0,0 +1,470 @@
+//! the behaviors experiments keep writing as user functions, with their directions
+//! and energies as parameters. Every action has a `run` method to call from a user
+//! function, every action is a [`WorldAction`] of its own and [`standard`] creates a
+//! few common setups by name.
+
+use rand::Rng;
+
+use super::{ActionContext, World, WorldAction};
+use crate::new2::entity::{Direction, GPCAEntity, GPCAEntityInternal};
+
+/// a neighboring cell an action needs in a certain state before it does anything.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Neighbor {
+    /// the cell in this direction is on the map and empty.
+    Free(Direction),
+    /// an entity is in the cell in this direction.
+    Occupied(Direction),
+}
+impl Neighbor {
+    pub fn holds(&self, world: &World, entity: &GPCAEntityInternal) -> bool {
+        match *self {
+            Neighbor::Free(direction) => world.position_at_direction(entity.pos(), direction).is_some_and(|[x, y]| !world.get(x, y)),
+            Neighbor::Occupied(direction) => world.get_entity_at_direction(entity, direction).is_some(),
+        }
+    }
+}
+
+/// where an action puts a new entity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// the cell in this direction, nothing is placed if it is taken.
+    Toward(Direction),
+    /// the first free neighboring cell in the order of [`Direction::ALL`].
+    FirstFree,
+    /// a free neighboring cell drawn from the world rng.
+    RandomFree,
+}
+impl Placement {
+    /// the cell a new neighbor of the entity that called the function goes into,
+    /// `None` if there is no free one.
+    pub fn cell(&self, ctx: &mut ActionContext) -> Option<[u32; 2]> {
+        let (world, entity) = (ctx.world(), ctx.entity().inner());
+        let free = |direction: &Direction| Neighbor::Free(*direction).holds(world, entity);
+        let direction = match *self {
+            Placement::Toward(direction) => free(&direction).then_some(direction)?,
+            Placement::FirstFree => *Direction::ALL.iter().find(|direction| free(direction))?,
+            Placement::RandomFree => {
+                let directions = Direction::ALL.into_iter().filter(free).collect::<Vec<_>>();
+                if directions.is_empty() {
+                    return None;
+                }
+                directions[ctx.rng().gen_range(0..directions.len())]
+            }
+        };
+        ctx.world().position_at_direction(ctx.entity().pos(), direction)
+    }
+}
+
+/// `ratio` of `energy`, rounded up.
+fn share(energy: u32, ratio: f64) -> u32 {
+    (energy as f64*ratio).ceil() as u32
+}
+
+/// takes the entity in `prey` off the map and gains a share of its energy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Eat {
+    /// what [`WorldAction::name`] reports.
+    pub name: &'static str,
+    pub prey: Direction,
+    /// share of the energy of the prey the eater gains, rounded up.
+    pub ratio: f64,
+    /// the eater never ends up with more energy than this, even if it had more.
+    pub cap: u32,
+    pub requires: &'static [Neighbor],
+}
+impl Eat {
+    pub fn run(&self, ctx: &mut ActionContext) {
+        let (world, entity) = (ctx.world(), ctx.entity());
+        if !self.requires.iter().all(|neighbor| neighbor.holds(world, entity.inner())) {
+            return;
+        }
+        let Some(prey) = world.get_entity_at_direction(entity.inner(), self.prey) else {
+            return;
+        };
+        let energy = entity.get_energy().saturating_add(share(prey.get_energy(), self.ratio)).min(self.cap);
+        let (handle, prey) = (entity.handle(), prey.handle());
+        ctx.eat(prey);
+        ctx.set_energy(handle, energy);
+    }
+}
+impl WorldAction for Eat {
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn run(&mut self, ctx: &mut ActionContext) {
+        Eat::run(self, ctx);
+    }
+}
+
+/// places a copy of the entity with random registers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reproduce {
+    /// what [`WorldAction::name`] reports.
+    pub name: &'static str,
+    pub placement: Placement,
+    /// energy of the copy, the parent keeps its own.
+    pub offspring_energy: u32,
+    /// whether the copy goes through the mutation pipeline, see
+    /// [`World::create_entity`].
+    pub mutate: bool,
+    pub requires: &'static [Neighbor],
+}
+impl Reproduce {
+    pub fn run(&self, ctx: &mut ActionContext) {
+        let (world, entity) = (ctx.world(), ctx.entity());
+        if !self.requires.iter().all(|neighbor| neighbor.holds(world, entity.inner())) {
+            return;
+        }
+        let Some([x, y]) = self.placement.cell(ctx) else {
+            return;
+        };
+        let (color, code) = (ctx.entity().color, ctx.entity().code().to_vec());
+        let child = GPCAEntity::new(x, y, ctx.rng().gen_range(0..u64::MAX), ctx.rng().gen_range(0..u64::MAX), self.offspring_energy, color, code);
+        if self.mutate {
+            ctx.spawn_mutated(child);
+        } else {
+            ctx.spawn(child);
+        }
+    }
+}
+impl WorldAction for Reproduce {
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn run(&mut self, ctx: &mut ActionContext) {
+        Reproduce::run(self, ctx);
+    }
+}
+
+/// places a child of the entity and the one in `partner`, with code crossed by the
+/// operator of the world, see [`World::cross`], and random registers.
+#[derive(Clone, Copy, Debug)]
+pub struct Breed {
+    /// what [`WorldAction::name`] reports.
+    pub name: &'static str,
+    pub partner: Direction,
+    pub placement: Placement,
+    pub offspring_energy: u32,
+    /// see [`Reproduce::mutate`].
+    pub mutate: bool,
+    /// the color of the child from the colors of the entity and its partner.
+    pub color: fn(u32, u32) -> u32,
+    pub requires: &'static [Neighbor],
+}
+impl Breed {
+    pub fn run(&self, ctx: &mut ActionContext) {
+        self.run_with_color(ctx, self.color);
+    }
+    /// [`Breed::run`] with the color of the child from `color` instead of
+    /// [`Breed::color`], for colors that depend on more than the parents.
+    pub fn run_with_color(&self, ctx: &mut ActionContext, color: impl FnOnce(u32, u32) -> u32) {
+        let (world, entity) = (ctx.world(), ctx.entity());
+        if !self.requires.iter().all(|neighbor| neighbor.holds(world, entity.inner())) {
+            return;
+        }
+        let Some(partner) = world.get_entity_at_direction(entity.inner(), self.partner) else {
+            return;
+        };
+        let (handle, partner, color) = (entity.handle(), partner.handle(), color(entity.color, partner.color));
+        let Some([x, y]) = self.placement.cell(ctx) else {
+            return;
+        };
+        let Some(code) = ctx.cross(handle, partner) else {
+            return;
+        };
+        let child = GPCAEntity::new(x, y, ctx.rng().gen_range(0..u64::MAX), ctx.rng().gen_range(0..u64::MAX), self.offspring_energy, color, code);
+        if self.mutate {
+            ctx.spawn_mutated(child);
+        } else {
+            ctx.spawn(child);
+        }
+    }
+}
+impl WorldAction for Breed {
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn run(&mut self, ctx: &mut ActionContext) {
+        Breed::run(self, ctx);
+    }
+}
+
+/// every channel of the colors averaged.
+pub fn mix_colors(a: u32, b: u32) -> u32 {
+    let channel = |shift: u32| ((a >> shift)&0xff)/2+((b >> shift)&0xff)/2+((a >> shift)&(b >> shift)&1);
+    channel(0)|channel(8) << 8|channel(16) << 16|channel(24) << 24
+}
+
+/// hands a share of the energy of the entity to the one in `direction`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShareEnergy {
+    /// what [`WorldAction::name`] reports.
+    pub name: &'static str,
+    pub direction: Direction,
+    /// share of its energy the entity gives, rounded up.
+    pub ratio: f64,
+    /// the receiver never ends up with more energy than this, the entity only gives
+    /// what the receiver can take.
+    pub cap: u32,
+    pub requires: &'static [Neighbor],
+}
+impl ShareEnergy {
+    pub fn run(&self, ctx: &mut ActionContext) {
+        let (world, entity) = (ctx.world(), ctx.entity());
+        if !self.requires.iter().all(|neighbor| neighbor.holds(world, entity.inner())) {
+            return;
+        }
+        let Some(receiver) = world.get_entity_at_direction(entity.inner(), self.direction) else {
+            return;
+        };
+        let given = share(entity.get_energy(), self.ratio).min(entity.get_energy()).min(self.cap.saturating_sub(receiver.get_energy()));
+        let (handle, energy, receiver, received) = (entity.handle(), entity.get_energy()-given, receiver.handle(), receiver.get_energy()+given);
+        ctx.set_energy(handle, energy);
+        ctx.set_energy(receiver, received);
+    }
+}
+impl WorldAction for ShareEnergy {
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn run(&mut self, ctx: &mut ActionContext) {
+        ShareEnergy::run(self, ctx);
+    }
+}
+
+/// eats the entity above if the cell below is free, keeping a quarter of its energy
+/// up to 4097.
+pub const EAT_TOP: Eat = Eat { name: "eat_top", prey: Direction::Top, ratio: 0.25, cap: 4097, requires: &[Neighbor::Free(Direction::Bottom)] };
+/// [`EAT_TOP`] upside down.
+pub const EAT_BOTTOM: Eat = Eat { name: "eat_bottom", prey: Direction::Bottom, ratio: 0.25, cap: 4097, requires: &[Neighbor::Free(Direction::Top)] };
+/// copies itself to the bottom right if an entity is at the top left.
+pub const REPRODUCE_BOTTOM_RIGHT: Reproduce = Reproduce {
+    name: "reproduce_bottom_right", placement: Placement::Toward(Direction::BottomRight), offspring_energy: 4096, mutate: false,
+    requires: &[Neighbor::Occupied(Direction::TopLeft)],
+};
+/// copies itself to the top if an entity is at the bottom.
+pub const REPRODUCE_TOP: Reproduce = Reproduce {
+    name: "reproduce_top", placement: Placement::Toward(Direction::Top), offspring_energy: 4096, mutate: false,
+    requires: &[Neighbor::Occupied(Direction::Bottom)],
+};
+/// breeds with the entity at the bottom right, the child goes to the top left.
+pub const BREED_TOP_LEFT: Breed = Breed {
+    name: "breed_top_left", partner: Direction::BottomRight, placement: Placement::Toward(Direction::TopLeft),
+    offspring_energy: 4096, mutate: true, color: mix_colors, requires: &[],
+};
+/// breeds with the entity at the top, the child goes to the bottom.
+pub const BREED_BOTTOM: Breed = Breed {
+    name: "breed_bottom", partner: Direction::Top, placement: Placement::Toward(Direction::Bottom),
+    offspring_energy: 4097, mutate: true, color: mix_colors, requires: &[],
+};
+/// gives a quarter of its energy to the entity to the right, up to 4096.
+pub const SHARE_RIGHT: ShareEnergy = ShareEnergy { name: "share_right", direction: Direction::Right, ratio: 0.25, cap: 4096, requires: &[] };
+
+/// the setups above by their names, for [`super::config::WorldConfig::build`].
+pub fn standard(name: &str) -> Option<Box<dyn WorldAction>> {
+    Some(match name {
+        "eat_top" => Box::new(EAT_TOP),
+        "eat_bottom" => Box::new(EAT_BOTTOM),
+        "reproduce_bottom_right" => Box::new(REPRODUCE_BOTTOM_RIGHT),
+        "reproduce_top" => Box::new(REPRODUCE_TOP),
+        "breed_top_left" => Box::new(BREED_TOP_LEFT),
+        "breed_bottom" => Box::new(BREED_BOTTOM),
+        "share_right" => Box::new(SHARE_RIGHT),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new2::world::{Crossover, CrossoverOperator, EntityHandle};
+
+    fn small_world() -> World {
+        World::new(vec![], 16, 4, 4, true, 0.0, Some(1))
+    }
+    fn add(world: &mut World, x: u32, y: u32, energy: u32) -> EntityHandle {
+        world.push_entity(GPCAEntity::new(x, y, 0, 0, energy, x|y << 8, vec![x, y, energy]))
+    }
+    /// runs `action` for the entity of `handle` and applies what it asked for.
+    fn act(world: &mut World, handle: EntityHandle, action: impl FnOnce(&mut ActionContext)) {
+        let idx = world.get_entites().iter().position(|entity| entity.handle() == handle).unwrap();
+        let mut ctx = ActionContext::new(world, idx);
+        action(&mut ctx);
+        ctx.apply();
+    }
+    fn energy(world: &World, handle: EntityHandle) -> Option<u32> {
+        world.entity(handle).map(|entity| entity.get_energy())
+    }
+
+    #[test]
+    fn standard_actions_are_named_after_their_setup() {
+        let names = ["eat_top", "eat_bottom", "reproduce_bottom_right", "reproduce_top", "breed_top_left", "breed_bottom", "share_right"];
+        for name in names {
+            assert_eq!(standard(name).unwrap().name(), name);
+        }
+        assert!(standard("eat").is_none());
+    }
+    #[test]
+    fn eating_gains_a_quarter_rounded_up() {
+        for prey_energy in (0..=40).chain([4095, 4096, 4097, u32::MAX]) {
+            let mut world = small_world();
+            let eater = add(&mut world, 1, 1, 100);
+            let prey = add(&mut world, 1, 2, prey_energy);
+            act(&mut world, eater, |ctx| EAT_TOP.run(ctx));
+            assert_eq!(energy(&world, eater), Some((100+prey_energy.div_ceil(4)).min(4097)), "prey with {prey_energy}");
+            assert!(!world.is_alive(prey));
+        }
+    }
+    #[test]
+    fn eating_is_capped() {
+        for (start, prey_energy) in [(4000, 4000), (4097, 4), (5000, 0)] {
+            let mut world = small_world();
+            let eater = add(&mut world, 1, 1, start);
+            add(&mut world, 1, 2, prey_energy);
+            act(&mut world, eater, |ctx| EAT_TOP.run(ctx));
+            assert_eq!(energy(&world, eater), Some(4097), "{start} eating {prey_energy}");
+        }
+    }
+    #[test]
+    fn eating_needs_prey_and_a_free_cell() {
+        // the cell below is taken
+        let mut world = small_world();
+        let eater = add(&mut world, 1, 1, 100);
+        let prey = add(&mut world, 1, 2, 100);
+        add(&mut world, 1, 0, 100);
+        act(&mut world, eater, |ctx| EAT_TOP.run(ctx));
+        assert_eq!((energy(&world, eater), energy(&world, prey)), (Some(100), Some(100)));
+        // the cell below is off the map
+        let mut world = small_world();
+        let eater = add(&mut world, 1, 0, 100);
+        let prey = add(&mut world, 1, 1, 100);
+        act(&mut world, eater, |ctx| EAT_TOP.run(ctx));
+        assert_eq!((energy(&world, eater), energy(&world, prey)), (Some(100), Some(100)));
+        // the prey would be off the map
+        let mut world = small_world();
+        let eater = add(&mut world, 1, 3, 100);
+        act(&mut world, eater, |ctx| EAT_TOP.run(ctx));
+        assert_eq!((energy(&world, eater), world.get_entites().len()), (Some(100), 1));
+    }
+    #[test]
+    fn reproducing_places_a_copy() {
+        let mut world = small_world();
+        let parent = add(&mut world, 1, 1, 10);
+        add(&mut world, 1, 0, 10);
+        act(&mut world, parent, |ctx| REPRODUCE_TOP.run(ctx));
+        let child = world.entity_at([1, 2]).unwrap();
+        assert_eq!((child.get_energy(), child.color, child.code()), (4096, 1|1 << 8, &[1, 1, 10][..]));
+        assert_eq!(energy(&world, parent), Some(10));
+    }
+    #[test]
+    fn reproducing_needs_a_free_target() {
+        // the target is taken
+        let mut world = small_world();
+        let parent = add(&mut world, 1, 1, 10);
+        add(&mut world, 1, 0, 10);
+        add(&mut world, 1, 2, 10);
+        act(&mut world, parent, |ctx| REPRODUCE_TOP.run(ctx));
+        assert_eq!(world.get_entites().len(), 3);
+        // the target is off the map
+        let mut world = small_world();
+        let parent = add(&mut world, 1, 3, 10);
+        add(&mut world, 1, 2, 10);
+        act(&mut world, parent, |ctx| REPRODUCE_TOP.run(ctx));
+        assert_eq!(world.get_entites().len(), 2);
+        // the requirement does not hold
+        let mut world = small_world();
+        let parent = add(&mut world, 1, 1, 10);
+        act(&mut world, parent, |ctx| REPRODUCE_TOP.run(ctx));
+        assert_eq!(world.get_entites().len(), 1);
+    }
+    #[test]
+    fn placements_pick_free_cells() {
+        let first_free = Reproduce { name: "reproduce_first_free", placement: Placement::FirstFree, requires: &[], ..REPRODUCE_TOP };
+        let mut world = small_world();
+        let parent = add(&mut world, 1, 1, 10);
+        add(&mut world, 2, 1, 10);
+        act(&mut world, parent, |ctx| first_free.run(ctx));
+        assert!(world.entity_at([2, 2]).is_some(), "top right follows right");
+        // a corner only has three neighbors on the map
+        let random_free = Reproduce { name: "reproduce_random", placement: Placement::RandomFree, ..first_free };
+        let mut world = small_world();
+        let parent = add(&mut world, 0, 0, 10);
+        add(&mut world, 1, 0, 10);
+        add(&mut world, 1, 1, 10);
+        act(&mut world, parent, |ctx| random_free.run(ctx));
+        assert!(world.entity_at([0, 1]).is_some());
+        act(&mut world, parent, |ctx| random_free.run(ctx));
+        act(&mut world, parent, |ctx| first_free.run(ctx));
+        assert_eq!(world.get_entites().len(), 4);
+    }
+    #[test]
+    fn breeding_crosses_the_partners() {
+        let mut world = small_world();
+        let parent = add(&mut world, 1, 1, 10);
+        let partner = add(&mut world, 2, 0, 20);
+        act(&mut world, parent, |ctx| BREED_TOP_LEFT.run(ctx));
+        let child = world.entity_at([0, 2]).unwrap();
+        let (a, b) = (world.entity(parent).unwrap(), world.entity(partner).unwrap());
+        let code = CrossoverOperator::Alternating.cross(a.code(), b.code(), &mut rand_pcg::Pcg64::new(0, 0));
+        assert_eq!((child.get_energy(), child.color, child.code()), (4096, mix_colors(a.color, b.color), &code[..]));
+    }
+    #[test]
+    fn breeding_to_the_bottom_gives_like_the_old_harness() {
+        let mut world = small_world();
+        let parent = add(&mut world, 1, 2, 10);
+        add(&mut world, 1, 3, 20);
+        act(&mut world, parent, |ctx| BREED_BOTTOM.run(ctx));
+        assert_eq!(world.entity_at([1, 1]).unwrap().get_energy(), 4097);
+    }
+    #[test]
+    fn breeding_needs_a_partner_and_a_free_target() {
+        // no partner
+        let mut world = small_world();
+        let parent = add(&mut world, 1, 1, 10);
+        act(&mut world, parent, |ctx| BREED_TOP_LEFT.run(ctx));
+        assert_eq!(world.get_entites().len(), 1);
+        // the target is taken
+        add(&mut world, 2, 0, 10);
+        add(&mut world, 0, 2, 10);
+        act(&mut world, parent, |ctx| BREED_TOP_LEFT.run(ctx));
+        assert_eq!(world.get_entites().len(), 3);
+        // the target is off the map
+        let mut world = small_world();
+        let parent = add(&mut world, 0, 1, 10);
+        add(&mut world, 1, 0, 10);
+        act(&mut world, parent, |ctx| BREED_TOP_LEFT.run(ctx));
+        assert_eq!(world.get_entites().len(), 2);
+    }
+    #[test]
+    fn sharing_gives_a_quarter_rounded_up() {
+        for given in 0..=40 {
+            let mut world = small_world();
+            let giver = add(&mut world, 1, 1, given);
+            let receiver = add(&mut world, 2, 1, 0);
+            act(&mut world, giver, |ctx| SHARE_RIGHT.run(ctx));
+            assert_eq!(energy(&world, receiver), Some(given.div_ceil(4)), "sharing {given}");
+            assert_eq!(energy(&world, giver), Some(given-given.div_ceil(4)));
+        }
+    }
+    #[test]
+    fn sharing_is_capped() {
+        for (received, expected) in [(4090, 4096), (4096, 4096), (5000, 5000)] {
+            let mut world = small_world();
+            let giver = add(&mut world, 1, 1, 100);
+            let receiver = add(&mut world, 2, 1, received);
+            act(&mut world, giver, |ctx| SHARE_RIGHT.run(ctx));
+            assert_eq!(energy(&world, receiver), Some(expected), "receiver with {received}");
+            assert_eq!(energy(&world, giver), Some(100-(expected-received)));
+        }
+    }
+    #[test]
+    fn sharing_needs_a_receiver() {
+        let mut world = small_world();
+        let giver = add(&mut world, 3, 1, 100);
+        act(&mut world, giver, |ctx| SHARE_RIGHT.run(ctx));
+        let lonely = add(&mut world, 1, 1, 100);
+        act(&mut world, lonely, |ctx| SHARE_RIGHT.run(ctx));
+        assert_eq!((energy(&world, giver), energy(&world, lonely)), (Some(100), Some(100)));
+    }
+}
@@ -0,0 +1,441 @@
+use std::{fmt::Display, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+
+use super::{CrossoverOperator, LayerRules, MutationPipeline, Neighborhood, CostModel, SignalRules, Schedule, StepMode, Topology, World, WorldAction};
+use crate::new2::entity::bytecode::NEIGHBORHOOD_SELECTORS;
+
+/// describes a complete experiment, everything [`World::new`] takes plus the
+/// starting population and how long and where a run writes its results.
+///
+/// ```toml
+/// width = 256
+/// height = 256
+/// seed = "0xabdf1327932123ffabdf1327932123ff"
+/// use_energy = true
+/// mutation_chance = 0.001
+/// crossover = "two_point"
+/// topology = "toroidal"
+/// schedule = "random"
+/// step_mode = "parallel"
+/// threads = 8
+/// functions = ["eat_top", "breed_top_left"]
+///
+/// [[neighborhoods]]
+/// shape = "moore"
+/// radius = 1
+///
+/// [[layers]]
+/// name = "food"
+/// initial = 128
+/// capacity = 256
+/// regrowth = 1
+/// harvest = 32
+///
+/// [signal]
+/// decay = 0.1
+/// diffusion = 0.2
+///
+/// [mutation]
+/// max_len = 128
+/// stages = [
+///     { chance = 0.001, operator = "flip_bit" },
+///     { chance = 0.01, operator = { duplicate = { max_segment = 8 } } },
+/// ]
+///
+/// [costs]
+/// budget = 4
+/// tick = 1
+/// events = { neighbors = 1 }
+/// responses = { move = 2, call = 8 }
+///
+/// [[neighborhoods]]
+/// shape = "von_neumann"
+/// radius = 3
+/// include_self = true
+///
+/// [population]
+/// entities = 1024
+/// energy = 4096
+/// code_len = 40
+///
+/// [output]
+/// steps = 10000
+/// interval = 250
+/// csv = "population.csv"
+/// metrics = "metrics.jsonl"
+/// density_block = 32
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorldConfig {
+    pub width: u32,
+    pub height: u32,
+    /// state of the world rng, [`World::new`] picks a fixed state if this is `None`.
+    /// Written as a string since 128 bit integers do not fit in toml, `None` is
+    /// written as the state it stands for since leaving it out means the default.
+    #[serde(with = "seed")]
+    pub seed: Option<u128>,
+    pub use_energy: bool,
+    pub mutation_chance: f64,
+    /// replaces the single bit flip `mutation_chance` sets up, which is ignored then.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mutation: Option<MutationPipeline>,
+    /// how [`World::cross`] combines the code of two parents.
+    pub crossover: CrossoverOperator,
+    pub topology: Topology,
+    /// the neighborhoods surrounding square events select from, `surround` counts in
+    /// the first one.
+    pub neighborhoods: Vec<Neighborhood>,
+    /// scalar fields next to the occupancy map, layer events select them in this order.
+    pub layers: Vec<LayerRules>,
+    pub signal: SignalRules,
+    /// the order entities are updated in every step.
+    pub schedule: Schedule,
+    /// instructions per step and their energy costs, one instruction for one energy
+    /// by default.
+    pub costs: CostModel,
+    pub step_mode: StepMode,
+    /// threads of a parallel step, defaults to the available parallelism.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threads: Option<usize>,
+    /// defaults to the size of the starting population.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity_capacity: Option<usize>,
+    /// names of the user functions in the order entities `Call` them.
+    pub functions: Vec<String>,
+    pub population: PopulationConfig,
+    pub output: OutputConfig,
+}
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PopulationConfig {
+    pub entities: usize,
+    pub energy: u32,
+    pub code_len: u32,
+}
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    pub steps: usize,
+    /// steps between two rows of statistics, see [`super::Metrics`].
+    pub interval: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub csv: Option<PathBuf>,
+    /// where to write the statistics as JSON Lines, with the genome lengths and the
+    /// density in full.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<PathBuf>,
+    /// side of the blocks density is counted in.
+    pub density_block: u32,
+    /// where to write a [`super::ReplayLog`] of the run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record: Option<PathBuf>,
+    /// where to write a trace of every instruction, JSON Lines for `.jsonl` files and
+    /// [`super::Compact`] records otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<PathBuf>,
+}
+/// the defaults match the graphical test harness.
+impl Default for WorldConfig {
+    fn default() -> Self {
+        Self {
+            width: 256,
+            height: 256,
+            seed: Some(0xabdf1327932123ffabdf1327932123ff),
+            use_energy: true,
+            mutation_chance: 1.0/1000.0,
+            mutation: None,
+            crossover: CrossoverOperator::Alternating,
+            topology: Topology::Bounded,
+            neighborhoods: vec![Neighborhood::default()],
+            layers: vec![],
+            signal: SignalRules::default(),
+            schedule: Schedule::Sequential,
+            costs: CostModel::flat(),
+            step_mode: StepMode::Sequential,
+            threads: None,
+            entity_capacity: None,
+            functions: vec![],
+            population: PopulationConfig::default(),
+            output: OutputConfig::default(),
+        }
+    }
+}
+impl Default for PopulationConfig {
+    fn default() -> Self {
+        Self { entities: 1024, energy: 4096, code_len: 40 }
+    }
+}
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self { steps: 10000, interval: 250, csv: None, metrics: None, density_block: 16, record: None, trace: None }
+    }
+}
+
+mod seed {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn parse(value: &str) -> Result<u128, std::num::ParseIntError> {
+        let value = value.replace('_', "");
+        match value.strip_prefix("0x") {
+            Some(hex) => u128::from_str_radix(hex, 16),
+            None => value.parse(),
+        }
+    }
+    pub fn serialize<S: Serializer>(seed: &Option<u128>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:#x}", seed.unwrap_or(super::World::DEFAULT_STATE)))
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u128>, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Seed {
+            Integer(u64),
+            String(String),
+        }
+        match Option::<Seed>::deserialize(deserializer)? {
+            None => Ok(None),
+            Some(Seed::Integer(seed)) => Ok(Some(seed as u128)),
+            Some(Seed::String(seed)) => parse(&seed).map(Some).map_err(|err| D::Error::custom(format!("invalid seed '{seed}': {err}"))),
+        }
+    }
+}
+pub use seed::parse as parse_seed;
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, std::io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    /// the file extension is neither `.toml` nor `.json`.
+    UnknownFormat(PathBuf),
+    /// a value is outside of the range the world supports.
+    Invalid { field: &'static str, reason: String },
+    UnknownFunction(String),
+}
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => write!(f, "could not read {}: {err}", path.display()),
+            ConfigError::Toml(err) => write!(f, "invalid toml: {err}"),
+            ConfigError::Json(err) => write!(f, "invalid json: {err}"),
+            ConfigError::UnknownFormat(path) => write!(f, "{} is neither a .toml nor a .json file", path.display()),
+            ConfigError::Invalid { field, reason } => write!(f, "invalid {field}: {reason}"),
+            ConfigError::UnknownFunction(name) => write!(f, "no user function is named '{name}'"),
+        }
+    }
+}
+impl std::error::Error for ConfigError {}
+
+impl WorldConfig {
+    pub fn from_toml(source: &str) -> Result<Self, ConfigError> {
+        let config: Self = toml::from_str(source).map_err(ConfigError::Toml)?;
+        config.validate()?;
+        Ok(config)
+    }
+    pub fn from_json(source: &str) -> Result<Self, ConfigError> {
+        let config: Self = serde_json::from_str(source).map_err(ConfigError::Json)?;
+        config.validate()?;
+        Ok(config)
+    }
+    /// reads a `.toml` or `.json` file depending on its extension.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path).map_err(|err| ConfigError::Io(path.to_path_buf(), err))?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml(&source),
+            Some("json") => Self::from_json(&source),
+            _ => Err(ConfigError::UnknownFormat(path.to_path_buf())),
+        }
+    }
+    pub fn to_toml(&self) -> String {
+        toml::to_string_pretty(self).unwrap()
+    }
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |field, reason: String| Err(ConfigError::Invalid { field, reason });
+        if self.width == 0 || self.height == 0 {
+            return invalid("size", format!("{}x{} has no cells", self.width, self.height));
+        }
+        let Some(cells) = self.width.checked_mul(self.height) else {
+            return invalid("size", format!("{}x{} cells do not fit in a u32", self.width, self.height));
+        };
+        if !(0.0..=1.0).contains(&self.mutation_chance) {
+            return invalid("mutation_chance", format!("{} is not between 0 and 1", self.mutation_chance));
+        }
+        if let Some(mutation) = &self.mutation {
+            if let Err(reason) = mutation.validate() {
+                return invalid("mutation", reason);
+            }
+            if !mutation.lengths().contains(&(self.population.code_len as usize)) {
+                return invalid("population.code_len", format!("{} is outside of the code lengths the mutation pipeline allows", self.population.code_len));
+            }
+        }
+        if self.neighborhoods.is_empty() || self.neighborhoods.len() > NEIGHBORHOOD_SELECTORS as usize {
+            return invalid("neighborhoods", format!("{} neighborhoods given, events can select between 1 and {NEIGHBORHOOD_SELECTORS}", self.neighborhoods.len()));
+        }
+        if let Some(neighborhood) = self.neighborhoods.iter().find(|neighborhood| neighborhood.radius > self.width.max(self.height)) {
+            return invalid("neighborhoods", format!("radius {} reaches past every cell of a {}x{} map", neighborhood.radius, self.width, self.height));
+        }
+        for (idx, layer) in self.layers.iter().enumerate() {
+            if layer.name.is_empty() {
+                return invalid("layers", format!("layer {idx} has no name"));
+            }
+            if self.layers[..idx].iter().any(|other| other.name == layer.name) {
+                return invalid("layers", format!("'{}' is used by more than one layer", layer.name));
+            }
+            if !(0.0..=1.0).contains(&layer.decay) || !(0.0..=1.0).contains(&layer.diffusion) {
+                return invalid("layers", format!("decay and diffusion of '{}' have to be between 0 and 1", layer.name));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.signal.decay) || !(0.0..=1.0).contains(&self.signal.diffusion) {
+            return invalid("signal", "decay and diffusion have to be between 0 and 1".to_string());
+        }
+        if self.costs.budget == 0 {
+            return invalid("costs.budget", "entities need at least one instruction per step".to_string());
+        }
+        if self.threads == Some(0) {
+            return invalid("threads", "must be at least 1".to_string());
+        }
+        if self.population.entities > cells as usize {
+            return invalid("population.entities", format!("{} entities do not fit in {cells} cells", self.population.entities));
+        }
+        if self.population.code_len == 0 {
+            return invalid("population.code_len", "entities need at least one instruction".to_string());
+        }
+        if self.output.interval == 0 {
+            return invalid("output.interval", "must be at least 1".to_string());
+        }
+        if self.output.density_block == 0 {
+            return invalid("output.density_block", "must be at least 1".to_string());
+        }
+        Ok(())
+    }
+    /// creates the world and its starting population. `registry` creates the action
+    /// for every name in [`WorldConfig::functions`], a name that is listed twice gets
+    /// two actions. [`super::actions::standard`] knows the standard actions.
+    pub fn build(&self, registry: impl Fn(&str) -> Option<Box<dyn WorldAction>>) -> Result<World, ConfigError> {
+        self.validate()?;
+        let functions = self.functions.iter().map(|name| {
+            registry(name).ok_or_else(|| ConfigError::UnknownFunction(name.clone()))
+        }).collect::<Result<Vec<_>, _>>()?;
+        let capacity = self.entity_capacity.unwrap_or(self.population.entities);
+        let mut world = World::new(functions, capacity, self.width, self.height, self.use_energy, self.mutation_chance, self.seed);
+        if let Some(mutation) = &self.mutation {
+            world.set_mutation(mutation.clone());
+        }
+        world.set_crossover(self.crossover);
+        world.set_topology(self.topology);
+        world.set_neighborhoods(self.neighborhoods.clone());
+        for layer in self.layers.iter() {
+            world.add_layer(layer.clone());
+        }
+        world.set_signal_rules(self.signal);
+        world.set_schedule(self.schedule);
+        world.set_costs(self.costs);
+        world.set_step_mode(self.step_mode);
+        if let Some(threads) = self.threads {
+            world.set_threads(threads);
+        }
+        world.populate(self.population.entities, self.population.energy, self.population.code_len);
+        Ok(world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the field the default config with `change` applied is rejected for.
+    fn rejected(change: impl FnOnce(&mut WorldConfig)) -> Option<&'static str> {
+        let mut config = WorldConfig::default();
+        change(&mut config);
+        match config.validate() {
+            Ok(()) => None,
+            Err(ConfigError::Invalid { field, .. }) => Some(field),
+            Err(err) => panic!("unexpected error {err}"),
+        }
+    }
+
+    /// the example in the documentation of [`WorldConfig`].
+    fn documented() -> String {
+        include_str!("config.rs").lines()
+            .map(|line| line.trim_start().strip_prefix("///").map(|line| line.strip_prefix(' ').unwrap_or(line)))
+            .skip_while(|line| *line != Some("```toml"))
+            .skip(1)
+            .take_while(|line| *line != Some("```"))
+            .map(|line| line.unwrap())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn sizes_have_to_fit() {
+        assert_eq!(rejected(|config| config.width = 0), Some("size"));
+        assert_eq!(rejected(|config| config.height = 0), Some("size"));
+        assert_eq!(rejected(|config| (config.width, config.height) = (65536, 65536)), Some("size"));
+        assert_eq!(rejected(|config| (config.width, config.height) = (65536, 65535)), None);
+    }
+    #[test]
+    fn chances_have_to_be_probabilities() {
+        assert_eq!(rejected(|config| config.mutation_chance = -0.1), Some("mutation_chance"));
+        assert_eq!(rejected(|config| config.mutation_chance = 1.5), Some("mutation_chance"));
+        assert_eq!(rejected(|config| config.mutation_chance = f64::NAN), Some("mutation_chance"));
+        assert_eq!(rejected(|config| config.mutation_chance = 1.0), None);
+        assert_eq!(rejected(|config| config.mutation = Some(MutationPipeline::flip_bit(2.0))), Some("mutation"));
+        assert_eq!(rejected(|config| config.mutation = Some(MutationPipeline { max_len: Some(8), ..MutationPipeline::flip_bit(0.1) })), Some("population.code_len"));
+    }
+    #[test]
+    fn neighborhoods_have_to_be_selectable() {
+        assert_eq!(rejected(|config| config.neighborhoods.clear()), Some("neighborhoods"));
+        assert_eq!(rejected(|config| config.neighborhoods = vec![Neighborhood::default(); 9]), Some("neighborhoods"));
+        assert_eq!(rejected(|config| config.neighborhoods = vec![Neighborhood::default(); 8]), None);
+        assert_eq!(rejected(|config| config.neighborhoods[0].radius = 257), Some("neighborhoods"));
+    }
+    #[test]
+    fn layers_need_distinct_names_and_rates() {
+        assert_eq!(rejected(|config| config.layers = vec![LayerRules::new("")]), Some("layers"));
+        assert_eq!(rejected(|config| config.layers = vec![LayerRules::new("food"), LayerRules::new("food")]), Some("layers"));
+        assert_eq!(rejected(|config| config.layers = vec![LayerRules::new("food"), LayerRules::new("light")]), None);
+        assert_eq!(rejected(|config| config.layers = vec![LayerRules { decay: 1.5, ..LayerRules::new("food") }]), Some("layers"));
+        assert_eq!(rejected(|config| config.layers = vec![LayerRules { diffusion: 1.5, ..LayerRules::new("food") }]), Some("layers"));
+        assert_eq!(rejected(|config| config.signal.diffusion = 1.5), Some("signal"));
+        assert_eq!(rejected(|config| config.signal.decay = -0.5), Some("signal"));
+    }
+    #[test]
+    fn runs_need_something_to_do() {
+        assert_eq!(rejected(|config| config.costs.budget = 0), Some("costs.budget"));
+        assert_eq!(rejected(|config| config.threads = Some(0)), Some("threads"));
+        assert_eq!(rejected(|config| config.threads = Some(1)), None);
+        assert_eq!(rejected(|config| config.population.entities = 256*256+1), Some("population.entities"));
+        assert_eq!(rejected(|config| config.population.entities = 256*256), None);
+        assert_eq!(rejected(|config| config.population.code_len = 0), Some("population.code_len"));
+        assert_eq!(rejected(|config| config.output.interval = 0), Some("output.interval"));
+        assert_eq!(rejected(|config| config.output.density_block = 0), Some("output.density_block"));
+    }
+    #[test]
+    fn the_documented_example_parses() {
+        let config = WorldConfig::from_toml(&documented()).unwrap();
+        assert_eq!(config.seed, Some(0xabdf1327932123ffabdf1327932123ff));
+        assert_eq!((config.step_mode, config.threads), (StepMode::Parallel, Some(8)));
+        assert_eq!(config.neighborhoods.len(), 2);
+        assert_eq!(config.layers[0].name, "food");
+        assert_eq!(config.mutation.as_ref().unwrap().stages.len(), 2);
+        assert_eq!((config.costs.budget, config.output.csv.as_deref()), (4, Some(Path::new("population.csv"))));
+    }
+    #[test]
+    fn configs_round_trip() {
+        let mut config = WorldConfig::from_toml(&documented()).unwrap();
+        config.output.record = Some("run.replay".into());
+        assert_eq!(WorldConfig::from_toml(&config.to_toml()).unwrap(), config);
+        assert_eq!(WorldConfig::from_json(&config.to_json()).unwrap(), config);
+        config.seed = None;
+        let seeded = WorldConfig { seed: Some(World::DEFAULT_STATE), ..config.clone() };
+        assert_eq!(WorldConfig::from_toml(&config.to_toml()).unwrap(), seeded);
+        assert_eq!(WorldConfig::from_json(&config.to_json()).unwrap(), seeded);
+        assert!(matches!(WorldConfig::from_toml("widht = 4"), Err(ConfigError::Toml(_))));
+        assert!(matches!(WorldConfig::from_json("{\"width\": 0}"), Err(ConfigError::Invalid { field: "size", .. })));
+    }
+}
@@ -0,0 +1,184 @@
+use super::{EntityHandle, World};
+use crate::new2::entity::GPCAEntity;
+
+/// a change a user function asked for, see [`ActionContext`].
+enum Command {
+    Spawn { entity: GPCAEntity, mutate: bool },
+    /// whether the entity was eaten, see [`super::Tally::eaten`].
+    Remove { entity: EntityHandle, eaten: bool },
+    SetEnergy(EntityHandle, u32),
+}
+
+/// what is called when an entity runs [`crate::new2::entity::bytecode::Response::Call`].
+/// Plain functions and closures taking an [`ActionContext`] are actions through the
+/// blanket impl, types that need parameters or keep state between calls implement it
+/// themselves.
+pub trait WorldAction: Send + Sync {
+    /// the name actions are logged by.
+    fn name(&self) -> &str;
+    /// while the action runs the world it sees has no actions.
+    fn run(&mut self, ctx: &mut ActionContext);
+    /// energy the calling entity pays before the action runs, on top of
+    /// [`super::ResponseCosts::call`]. Only paid in worlds that use energy.
+    fn cost(&self) -> u32 {
+        0
+    }
+}
+/// named after their type, which is the path of a function item but the same
+/// signature for every [`super::WorldUserFunction`] and a compiler generated path for
+/// closures. Wrap them in [`Named`] to tell them apart.
+impl<F> WorldAction for F
+    where F: FnMut(&mut ActionContext) + Send + Sync {
+    fn name(&self) -> &str {
+        std::any::type_name::<F>()
+    }
+    fn run(&mut self, ctx: &mut ActionContext) {
+        self(ctx);
+    }
+}
+
+/// a function or closure with the name it is logged by.
+#[derive(Clone, Copy, Debug)]
+pub struct Named<F> {
+    pub name: &'static str,
+    pub action: F,
+}
+impl<F> WorldAction for Named<F>
+    where F: FnMut(&mut ActionContext) + Send + Sync {
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn run(&mut self, ctx: &mut ActionContext) {
+        (self.action)(ctx);
+    }
+}
+
+/// what a [`WorldAction`] works with. The world and the entity that
+/// called the function can only be read, changes are queued and applied in the order
+/// they were asked for right after the function returns, before the next entity
+/// steps. Drawing from the rng is the one thing that happens right away.
+pub struct ActionContext<'a> {
+    world: &'a mut World,
+    entity: usize,
+    commands: Vec<Command>,
+}
+impl<'a> ActionContext<'a> {
+    pub(crate) fn new(world: &'a mut World, entity: usize) -> Self {
+        Self { world, entity, commands: vec![] }
+    }
+    pub fn world(&self) -> &World {
+        self.world
+    }
+    /// the entity that called the function.
+    pub fn entity(&self) -> &GPCAEntity {
+        &self.world.entities[self.entity]
+    }
+    pub fn rng(&mut self) -> &mut rand_pcg::Pcg64 {
+        &mut self.world.pseudo
+    }
+    /// the code of a child of `a` and `b`, see [`World::cross`].
+    pub fn cross(&mut self, a: EntityHandle, b: EntityHandle) -> Option<Vec<u32>> {
+        self.world.cross(a, b)
+    }
+    /// adds `entity` to the world, unless its cell is occupied by the time the
+    /// commands are applied.
+    pub fn spawn(&mut self, entity: GPCAEntity) {
+        self.commands.push(Command::Spawn { entity, mutate: false });
+    }
+    /// like [`ActionContext::spawn`] but the code may mutate first, see
+    /// [`World::create_entity`].
+    pub fn spawn_mutated(&mut self, entity: GPCAEntity) {
+        self.commands.push(Command::Spawn { entity, mutate: true });
+    }
+    /// takes the entity off the map. Once the commands are applied its handle no
+    /// longer resolves and the entity itself is dropped on its next turn.
+    pub fn remove(&mut self, entity: EntityHandle) {
+        self.commands.push(Command::Remove { entity, eaten: false });
+    }
+    /// like [`ActionContext::remove`] but the entity counts as eaten.
+    pub fn eat(&mut self, entity: EntityHandle) {
+        self.commands.push(Command::Remove { entity, eaten: true });
+    }
+    pub fn set_energy(&mut self, entity: EntityHandle, energy: u32) {
+        self.commands.push(Command::SetEnergy(entity, energy));
+    }
+    pub(crate) fn apply(self) {
+        let world = self.world;
+        for command in self.commands {
+            match command {
+                Command::Spawn { entity, mutate } => {
+                    if world.get(entity.x(), entity.y()) {
+                        continue;
+                    }
+                    if mutate {
+                        world.create_entity(entity);
+                    } else {
+                        world.push_entity(entity);
+                    }
+                }
+                Command::Remove { entity, eaten } => {
+                    if let Some([x, y]) = world.entity(entity).map(|entity| entity.pos()) {
+                        world.remove(x, y);
+                        if eaten {
+                            world.tally.eaten += 1;
+                        }
+                    }
+                }
+                Command::SetEnergy(handle, energy) => {
+                    if let Some(entity) = world.entity_mut(handle) {
+                        entity.set_energy(energy);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(x: u32, y: u32, color: u32) -> GPCAEntity {
+        GPCAEntity::new(x, y, 0, 0, 100, color, vec![0])
+    }
+
+    #[test]
+    fn named_actions_keep_their_name() {
+        fn a(_: &mut ActionContext) {}
+        fn b(_: &mut ActionContext) {}
+        let (a, b): (super::super::WorldUserFunction, super::super::WorldUserFunction) = (a, b);
+        assert_eq!(a.name(), b.name());
+        assert_eq!((Named { name: "a", action: a }.name(), Named { name: "b", action: b }.name()), ("a", "b"));
+    }
+    #[test]
+    fn spawns_into_occupied_cells_are_skipped() {
+        let mut world = World::new(vec![], 4, 4, 4, true, 0.0, Some(1));
+        world.push_entity(entity(1, 1, 1));
+        world.push_entity(entity(2, 1, 2));
+        let mut ctx = ActionContext::new(&mut world, 0);
+        ctx.spawn(entity(2, 1, 3));
+        ctx.spawn(entity(3, 3, 4));
+        ctx.spawn(entity(3, 3, 5));
+        ctx.apply();
+        assert_eq!(world.get_entites().iter().map(|entity| entity.color).collect::<Vec<_>>(), [1, 2, 4]);
+        assert_eq!(world.tally().births, 3);
+    }
+    #[test]
+    fn removed_entities_lose_their_handle() {
+        let mut world = World::new(vec![], 4, 4, 4, true, 0.0, Some(1));
+        world.push_entity(entity(1, 1, 1));
+        let prey = world.push_entity(entity(1, 2, 2));
+        let mut ctx = ActionContext::new(&mut world, 0);
+        ctx.remove(prey);
+        ctx.set_energy(prey, 7);
+        assert!(ctx.world().is_alive(prey), "commands wait until the action returns");
+        ctx.apply();
+        assert!(!world.is_alive(prey));
+        assert!(world.entity(prey).is_none());
+        assert!(!world.get(1, 2));
+        let next = world.push_entity(entity(1, 2, 3));
+        assert_ne!(next, prey);
+        assert!(world.entity(prey).is_none());
+        assert_eq!(world.entity(next).map(|entity| entity.color), Some(3));
+    }
+}
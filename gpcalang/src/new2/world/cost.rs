@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+
+use crate::new2::entity::bytecode::{Event, Response, ResponseKind};
+
+/// how much an entity may do in a step and what it costs in energy. Costs are only
+/// paid in worlds that use energy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CostModel {
+    /// instructions an entity runs per step at most. Its turn ends early once an
+    /// instruction changes the world, e.g. moves or calls a user function, or once it
+    /// runs out of energy. The first instruction of a turn always runs.
+    pub budget: u32,
+    /// paid once per step by every entity before its first instruction.
+    pub tick: u32,
+    /// paid for every event that is evaluated.
+    pub events: EventCosts,
+    /// paid for every response that runs, i.e. whose event was true.
+    pub responses: ResponseCosts,
+}
+/// what evaluating an event costs, by the kind of event.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventCosts {
+    pub unconditional: u32,
+    /// comparing two registers.
+    pub compare: u32,
+    /// counting the occupied cells of a neighborhood.
+    pub neighbors: u32,
+    pub layer: u32,
+    pub signal: u32,
+}
+/// what running a response costs, by its [`ResponseKind`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ResponseCosts {
+    pub nop: u32,
+    pub arithmetic: u32,
+    pub jump: u32,
+    #[serde(rename = "move")]
+    pub movement: u32,
+    pub call: u32,
+    pub harvest: u32,
+    pub signal: u32,
+}
+/// the flat model, see [`CostModel::flat`].
+impl Default for CostModel {
+    fn default() -> Self {
+        Self::flat()
+    }
+}
+impl CostModel {
+    /// one instruction per step for one energy, regardless of the instruction.
+    pub fn flat() -> Self {
+        Self { budget: 1, tick: 1, events: EventCosts::default(), responses: ResponseCosts::default() }
+    }
+}
+impl EventCosts {
+    pub fn of(&self, event: &Event) -> u32 {
+        match event {
+            Event::Unconditional => self.unconditional,
+            Event::Equal(..) | Event::NotEqual(..) | Event::Greater(..) |
+            Event::Lesser(..) | Event::GreaterEqual(..) | Event::LesserEqual(..) => self.compare,
+            Event::SurroundingSquaresEqual(..) | Event::SurroundingSquaresNotEqual(..) |
+            Event::SurroundingSquaresGreater(..) | Event::SurroundingSquaresLesser(..) |
+            Event::SurroundingSquaresGreaterEqual(..) | Event::SurroundingSquaresLesserEqual(..) => self.neighbors,
+            Event::LayerEqual(..) | Event::LayerNotEqual(..) | Event::LayerGreater(..) |
+            Event::LayerLesser(..) | Event::LayerGreaterEqual(..) | Event::LayerLesserEqual(..) => self.layer,
+            Event::SignalEqual(..) | Event::SignalNotEqual(..) | Event::SignalGreater(..) |
+            Event::SignalLesser(..) | Event::SignalGreaterEqual(..) | Event::SignalLesserEqual(..) => self.signal,
+        }
+    }
+}
+impl ResponseCosts {
+    pub fn of(&self, response: &Response) -> u32 {
+        match response.kind() {
+            ResponseKind::Nop => self.nop,
+            ResponseKind::Arithmetic => self.arithmetic,
+            ResponseKind::Jump => self.jump,
+            ResponseKind::Move => self.movement,
+            ResponseKind::Call => self.call,
+            ResponseKind::Harvest => self.harvest,
+            ResponseKind::Signal => self.signal,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new2::entity::{asm::assemble, Effect, GPCAEntity};
+    use crate::new2::world::{actions, LayerRules, World, WorldAction};
+
+    fn functions() -> Vec<Box<dyn WorldAction>> {
+        vec![Box::new(actions::EAT_TOP), Box::new(actions::BREED_TOP_LEFT), Box::new(actions::REPRODUCE_TOP)]
+    }
+    fn save(world: &World) -> Vec<u8> {
+        let mut bytes = vec![];
+        world.save(&mut bytes).unwrap();
+        bytes
+    }
+    /// a step the way worlds stepped before cost models: every entity pays one energy
+    /// and runs a single instruction.
+    fn step_one_instruction(world: &mut World) {
+        let (mut clear, mut place) = (|_: &GPCAEntity| {}, |_: &GPCAEntity| {});
+        world.schedule_entities();
+        let mut i = 0;
+        while i < world.entities.len() {
+            let handle = world.entities[i].handle();
+            if world.entities[i].get_energy() == 0 {
+                if world.is_alive(handle) {
+                    world.remove(world.entities[i].x(), world.entities[i].y());
+                }
+            } else {
+                world.entities[i].decrement_energy();
+            }
+            if !world.is_alive(handle) {
+                world.slots.remove(handle);
+                world.entities.swap_remove(i);
+                if let Some(entity) = world.entities.get(i) {
+                    world.slots.set(entity.handle(), i);
+                }
+                continue;
+            }
+            let mut entity = *world.entities[i].inner();
+            let program = world.entities[i].program();
+            let event_response = entity.fetch(program.decoded());
+            let effect = if entity.handle_event(event_response.event, world) {
+                entity.handle_response(event_response.response, program.len())
+            } else {
+                Effect::None
+            };
+            *world.entities[i].inner_mut() = entity;
+            world.perform(i, effect, &mut clear, &mut place);
+            i += 1;
+        }
+        world.update_layers();
+        world.steps += 1;
+    }
+    /// a lone entity running `source` with 100 energy and zeroed registers.
+    fn lone(source: &str, costs: CostModel) -> World {
+        let mut world = World::new(vec![], 1, 8, 8, true, 0.0, Some(1));
+        world.set_costs(costs);
+        world.push_entity(GPCAEntity::new(4, 4, 0, 0, 100, 0, assemble(source).unwrap()));
+        world
+    }
+
+    #[test]
+    fn flat_costs_step_like_one_instruction_per_energy() {
+        let world = || {
+            let mut world = World::new(functions(), 512, 48, 48, true, 0.01, Some(5));
+            world.add_layer(LayerRules { initial: 20, capacity: 100, regrowth: 2, diffusion: 0.1, harvest: 10, ..LayerRules::new("food") });
+            world.populate(400, 60, 24);
+            world
+        };
+        let (mut flat, mut reference) = (world(), world());
+        assert_eq!(flat.costs(), &CostModel::flat());
+        for step in 0..60 {
+            flat.step(|_| {}, |_| {});
+            step_one_instruction(&mut reference);
+            assert!(save(&flat) == save(&reference), "diverged at step {step}");
+        }
+        assert!(!flat.get_entites().is_empty());
+    }
+    #[test]
+    fn budgets_end_after_world_changing_instructions() {
+        let mut world = lone("nop\nnop\nmove 0\nnop\nnop", CostModel { budget: 10, ..CostModel::flat() });
+        world.step(|_| {}, |_| {});
+        let entity = &world.get_entites()[0];
+        assert_eq!((entity.pos(), entity.inner().rip()), ([5, 4], 3));
+        assert_eq!(world.tally().usage.total(), 3);
+        assert_eq!(entity.get_energy(), 99);
+    }
+    #[test]
+    fn budgets_end_when_energy_runs_out() {
+        let costs = CostModel { budget: 10, tick: 0, responses: ResponseCosts { nop: 40, ..ResponseCosts::default() }, ..CostModel::flat() };
+        let mut world = lone("nop", costs);
+        world.step(|_| {}, |_| {});
+        assert_eq!((world.tally().usage.nop, world.get_entites()[0].get_energy()), (3, 0));
+    }
+    #[test]
+    fn events_and_responses_are_charged() {
+        let costs = CostModel {
+            budget: 1,
+            tick: 1,
+            events: EventCosts { compare: 2, ..EventCosts::default() },
+            responses: ResponseCosts { arithmetic: 5, ..ResponseCosts::default() },
+        };
+        let mut world = lone("if r0 == r1 : add r0, r1", costs);
+        world.step(|_| {}, |_| {});
+        assert_eq!(world.get_entites()[0].get_energy(), 100-1-2-5);
+        // a false event does not pay for its response
+        let mut world = lone("if r0 != r1 : add r0, r1", costs);
+        world.step(|_| {}, |_| {});
+        assert_eq!(world.get_entites()[0].get_energy(), 100-1-2);
+    }
+}
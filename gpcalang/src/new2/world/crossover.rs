@@ -0,0 +1,187 @@
+use std::{fmt::Display, str::FromStr};
+
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use super::{EntityHandle, World};
+
+/// the bits of a code word that hold the event and response opcodes, the rest are
+/// the two ext bytes.
+const OPCODES: u32 = 0xff00ff00;
+
+/// combines the code of two parents into the code of their child.
+pub trait Crossover {
+    /// the code of the child of `a` and `b`. Every random choice is drawn from `rng`,
+    /// so the same rng state crosses the same parents the same way.
+    fn cross(&self, a: &[u32], b: &[u32], rng: &mut dyn RngCore) -> Vec<u32>;
+}
+
+/// the crossover operators a world can breed with, see [`World::cross`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrossoverOperator {
+    /// takes the words alternately from `a` and `b`, starting with `a`. The child is
+    /// as long as the longer parent, the shorter one wraps around.
+    #[default]
+    Alternating,
+    /// takes every word from either parent with equal chance. The child is as long as
+    /// a parent drawn at random, past the end of the other parent words come from
+    /// this one.
+    Uniform,
+    /// the words of `a` up to a random point and the words of `b` after it.
+    OnePoint,
+    /// the words of `a` with the words of `b` between two random points.
+    TwoPoint,
+    /// like [`CrossoverOperator::OnePoint`] but the point is at a position where both
+    /// parents have the same opcodes, so the child changes parents between two
+    /// matching instructions. Parents that match nowhere have a copy of `a` as child.
+    Homologous,
+}
+impl Crossover for CrossoverOperator {
+    fn cross(&self, a: &[u32], b: &[u32], rng: &mut dyn RngCore) -> Vec<u32> {
+        if a.is_empty() || b.is_empty() {
+            return if a.is_empty() { b.to_vec() } else { a.to_vec() };
+        }
+        let shorter = a.len().min(b.len());
+        match self {
+            CrossoverOperator::Alternating => (0..a.len().max(b.len()))
+                .map(|idx| if idx%2 == 0 { a[idx%a.len()] } else { b[idx%b.len()] })
+                .collect(),
+            CrossoverOperator::Uniform => {
+                let len = if rng.gen_bool(0.5) { a.len() } else { b.len() };
+                (0..len).map(|idx| match (a.get(idx), b.get(idx)) {
+                    (Some(a), Some(b)) => if rng.gen_bool(0.5) { *a } else { *b },
+                    (Some(word), None) | (None, Some(word)) => *word,
+                    (None, None) => unreachable!(),
+                }).collect()
+            }
+            CrossoverOperator::OnePoint => {
+                let point = rng.gen_range(0..=shorter);
+                [&a[..point], &b[point..]].concat()
+            }
+            CrossoverOperator::TwoPoint => {
+                let (first, second) = (rng.gen_range(0..=shorter), rng.gen_range(0..=shorter));
+                let (start, end) = (first.min(second), first.max(second));
+                [&a[..start], &b[start..end], &a[end..]].concat()
+            }
+            CrossoverOperator::Homologous => {
+                let matching = (0..shorter).filter(|idx| a[*idx]&OPCODES == b[*idx]&OPCODES).collect::<Vec<_>>();
+                if matching.is_empty() {
+                    return a.to_vec();
+                }
+                let point = matching[rng.gen_range(0..matching.len())];
+                [&a[..point], &b[point..]].concat()
+            }
+        }
+    }
+}
+impl Display for CrossoverOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            CrossoverOperator::Alternating => "alternating",
+            CrossoverOperator::Uniform => "uniform",
+            CrossoverOperator::OnePoint => "one_point",
+            CrossoverOperator::TwoPoint => "two_point",
+            CrossoverOperator::Homologous => "homologous",
+        })
+    }
+}
+impl FromStr for CrossoverOperator {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "alternating" => Ok(CrossoverOperator::Alternating),
+            "uniform" => Ok(CrossoverOperator::Uniform),
+            "one_point" => Ok(CrossoverOperator::OnePoint),
+            "two_point" => Ok(CrossoverOperator::TwoPoint),
+            "homologous" => Ok(CrossoverOperator::Homologous),
+            _ => Err(format!("expected alternating, uniform, one_point, two_point or homologous, found '{s}'")),
+        }
+    }
+}
+
+impl World {
+    /// the code of a child of the entities `a` and `b` refer to, crossed with the
+    /// operator of the world and drawn from the world rng. `None` if either is gone.
+    pub fn cross(&mut self, a: EntityHandle, b: EntityHandle) -> Option<Vec<u32>> {
+        self.entity(a)?;
+        self.entity(b)?;
+        let (a, b) = (self.slots.get(a)?, self.slots.get(b)?);
+        Some(self.crossover.cross(self.entities[a].code(), self.entities[b].code(), &mut self.pseudo))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_pcg::Pcg64;
+
+    /// the interleaving the graphical test harness bred with before crossover
+    /// operators, kept as it was.
+    fn maleable_breed(a: &[u32], b: &[u32]) -> Vec<u32> {
+        let len = a.len().max(b.len());
+        let mut new_code = Vec::with_capacity(len);
+        let mut interleave = false;
+        for i in 0..len {
+            if interleave {
+                new_code.push(b[i%b.len()]);
+                interleave = false;
+            } else {
+                new_code.push(a[i%a.len()]);
+                interleave = true;
+            }
+        }
+        new_code
+    }
+    /// parents whose words tell which one they came from by their ext byte but
+    /// share every opcode.
+    fn parents(a_len: usize, b_len: usize) -> (Vec<u32>, Vec<u32>) {
+        let word = |parent: u32, idx: usize| parent<<16|(idx as u32%3)<<8|idx as u32;
+        ((0..a_len).map(|idx| word(0xaa, idx)).collect(), (0..b_len).map(|idx| word(0xbb, idx)).collect())
+    }
+
+    #[test]
+    fn alternating_matches_maleable_breed() {
+        let mut rng = Pcg64::new(1, 0xa02bdbf7bb3c0a7ac28fa16a64abf96);
+        for a_len in 1..=9 {
+            for b_len in 1..=9 {
+                let (a, b) = parents(a_len, b_len);
+                assert_eq!(CrossoverOperator::Alternating.cross(&a, &b, &mut rng), maleable_breed(&a, &b), "{a_len} and {b_len} words");
+            }
+        }
+    }
+    #[test]
+    fn children_take_every_word_from_a_parent() {
+        let mut rng = Pcg64::new(2, 0xa02bdbf7bb3c0a7ac28fa16a64abf96);
+        for operator in [CrossoverOperator::Uniform, CrossoverOperator::OnePoint, CrossoverOperator::TwoPoint, CrossoverOperator::Homologous] {
+            for a_len in 1..=9 {
+                for b_len in 1..=9 {
+                    let (a, b) = parents(a_len, b_len);
+                    for _ in 0..20 {
+                        let child = operator.cross(&a, &b, &mut rng);
+                        assert!(!child.is_empty(), "{operator} of {a_len} and {b_len} words");
+                        for (idx, word) in child.iter().enumerate() {
+                            assert!(a.get(idx) == Some(word) || b.get(idx) == Some(word), "{operator} of {a_len} and {b_len} words: {child:x?}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+    #[test]
+    fn homologous_parents_without_matches_copy_a() {
+        let mut rng = Pcg64::new(3, 0xa02bdbf7bb3c0a7ac28fa16a64abf96);
+        let (a, b) = parents(6, 4);
+        let b = b.iter().map(|word| word^0x0100_0000).collect::<Vec<_>>();
+        assert_eq!(CrossoverOperator::Homologous.cross(&a, &b, &mut rng), a);
+    }
+    #[test]
+    fn empty_parents_give_the_other_one() {
+        let mut rng = Pcg64::new(4, 0xa02bdbf7bb3c0a7ac28fa16a64abf96);
+        let (a, _) = parents(5, 0);
+        for operator in [CrossoverOperator::Alternating, CrossoverOperator::Uniform, CrossoverOperator::OnePoint, CrossoverOperator::TwoPoint, CrossoverOperator::Homologous] {
+            assert_eq!(operator.cross(&a, &[], &mut rng), a);
+            assert_eq!(operator.cross(&[], &a, &mut rng), a);
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use std::fmt::Display;
+
+use super::{EntityHandle, World};
+use crate::new2::entity::{bytecode::{Event, Register, Response, ResponseKind}, Effect, EventResponse, GPCAEntityInternal, Program};
+
+/// where [`Debugger::resume`] stops.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// before the instruction at this index runs.
+    Instruction(usize),
+    /// before an instruction with a response of this kind runs, whether its event
+    /// turns out true or not.
+    Response(ResponseKind),
+}
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Breakpoint::Instruction(index) => write!(f, "instruction {index}"),
+            Breakpoint::Response(kind) => write!(f, "response {kind}"),
+        }
+    }
+}
+
+/// why [`Debugger::resume`] returned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stop {
+    /// the next instruction hits the breakpoint at this index.
+    Breakpoint(usize),
+    /// the entity died or was taken off the map.
+    Gone,
+    /// the instruction limit was reached.
+    Limit,
+}
+
+/// one instruction an entity ran, see [`World::step_instruction`].
+#[derive(Clone, Copy, Debug)]
+pub struct Executed {
+    /// index of the instruction in the code.
+    pub index: usize,
+    /// the code word the instruction was decoded from.
+    pub word: u32,
+    pub event: Event,
+    /// whether the event was true and the response ran.
+    pub taken: bool,
+    pub response: Response,
+    /// what the world was asked to do, a blocked move is still a move.
+    pub effect: Effect,
+    /// the entity right before the instruction.
+    pub before: GPCAEntityInternal,
+    /// the entity after the instruction and its effect.
+    pub after: GPCAEntityInternal,
+}
+impl Executed {
+    pub(crate) fn new(before: GPCAEntityInternal, after: GPCAEntityInternal, program: &Program, event_response: EventResponse, taken: bool, effect: Effect) -> Self {
+        let index = before.next_index(program.len());
+        let EventResponse { event, response } = event_response;
+        Self { index, word: program.code()[index], event, taken, response, effect, before, after }
+    }
+    /// registers the instruction changed, a byte register is listed next to the 64 bit
+    /// register it is part of.
+    pub fn changed_registers(&self) -> impl Iterator<Item = Register> + '_ {
+        Register::ALL.into_iter().filter(|register| self.before.get(*register) != self.after.get(*register))
+    }
+}
+/// the instruction in assembly followed by what it changed, e.g.
+/// `   3  move r0  ; taken, moved to 4,7, energy 90 -> 89`.
+impl Display for Executed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:4}  ", self.index)?;
+        if self.event == Event::Unconditional {
+            write!(f, "{}", self.response)?;
+        } else {
+            write!(f, "{} {}", self.event, self.response)?;
+        }
+        f.write_str(if self.taken { "  ; taken" } else { "  ; not taken" })?;
+        for register in self.changed_registers() {
+            write!(f, ", {register} = {:#x}", self.after.get(register))?;
+        }
+        if self.before.pos() != self.after.pos() {
+            write!(f, ", moved to {},{}", self.after.x(), self.after.y())?;
+        }
+        if self.before.get_energy() != self.after.get_energy() {
+            write!(f, ", energy {} -> {}", self.before.get_energy(), self.after.get_energy())?;
+        }
+        Ok(())
+    }
+}
+
+impl World {
+    /// runs the next instruction of the entity `handle` refers to on its own. The
+    /// instruction costs what it costs in a step, but nothing else of a step happens:
+    /// there is no tick cost, other entities and layers do not change and no map
+    /// callbacks are called. `None` if the entity is gone.
+    pub fn step_instruction(&mut self, handle: EntityHandle) -> Option<Executed> {
+        self.entity(handle)?;
+        let idx = self.slots.get(handle)?;
+        let before = *self.entities[idx].inner();
+        let mut entity = before;
+        let (event_response, taken, effect) = self.execute(&mut entity, self.entities[idx].program());
+        *self.entities[idx].inner_mut() = entity;
+        self.perform(idx, effect, &mut |_| {}, &mut |_| {});
+        let executed = Executed::new(before, *self.entities[idx].inner(), self.entities[idx].program(), event_response, taken, effect);
+        self.tally.usage.count(&executed);
+        Some(executed)
+    }
+}
+
+/// follows a single entity instruction by instruction, see
+/// [`World::step_instruction`].
+#[derive(Clone, Debug, Default)]
+pub struct Debugger {
+    entity: EntityHandle,
+    breakpoints: Vec<Breakpoint>,
+    watches: Vec<Register>,
+}
+impl Debugger {
+    pub fn new(entity: EntityHandle) -> Self {
+        Self { entity, breakpoints: vec![], watches: vec![] }
+    }
+    pub fn entity(&self) -> EntityHandle {
+        self.entity
+    }
+    /// follows another entity, breakpoints and watches are kept.
+    pub fn attach(&mut self, entity: EntityHandle) {
+        self.entity = entity;
+    }
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+    pub fn remove_breakpoint(&mut self, idx: usize) -> Option<Breakpoint> {
+        (idx < self.breakpoints.len()).then(|| self.breakpoints.remove(idx))
+    }
+    pub fn watches(&self) -> &[Register] {
+        &self.watches
+    }
+    pub fn watch(&mut self, register: Register) {
+        if !self.watches.contains(&register) {
+            self.watches.push(register);
+        }
+    }
+    pub fn unwatch(&mut self, register: Register) {
+        self.watches.retain(|watch| *watch != register);
+    }
+    /// the watched registers of the entity with their values, `None` if it is gone.
+    pub fn watched(&self, world: &World) -> Option<Vec<(Register, u64)>> {
+        let entity = world.entity(self.entity)?;
+        Some(self.watches.iter().map(|register| (*register, entity.inner().get(*register))).collect())
+    }
+    /// the breakpoint the next instruction of the entity hits.
+    pub fn hit(&self, world: &World) -> Option<usize> {
+        let entity = world.entity(self.entity)?;
+        let index = entity.next_index();
+        let kind = entity.program().decoded().get(index).map(|instruction| instruction.response.kind());
+        self.breakpoints.iter().position(|breakpoint| match breakpoint {
+            Breakpoint::Instruction(at) => *at == index,
+            Breakpoint::Response(response) => Some(*response) == kind,
+        })
+    }
+    pub fn step(&self, world: &mut World) -> Option<Executed> {
+        world.step_instruction(self.entity)
+    }
+    /// runs instructions of the entity until the next one hits a breakpoint, at most
+    /// `limit` of them. The instruction the entity is at runs even if it is on a
+    /// breakpoint, so resuming from a breakpoint moves on.
+    pub fn resume(&self, world: &mut World, limit: usize) -> Stop {
+        for ran in 0..limit {
+            if ran != 0 {
+                if let Some(breakpoint) = self.hit(world) {
+                    return Stop::Breakpoint(breakpoint);
+                }
+            }
+            if self.step(world).is_none() {
+                return Stop::Gone;
+            }
+        }
+        if world.is_alive(self.entity) { Stop::Limit } else { Stop::Gone }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new2::entity::{asm::assemble, GPCAEntity};
+
+    /// a world with one entity at 1,1 counting r0 up by r1 before it moves right.
+    fn counting_world() -> (World, EntityHandle) {
+        let code = assemble("start:\n    add r0, r1\n    add r0, r1\n    move 0\n    jmp start").unwrap();
+        let mut world = World::new(vec![], 1, 5, 5, true, 0.0, Some(1));
+        let entity = world.push_entity(GPCAEntity::new(1, 1, 0, 1, 100, 0, code));
+        (world, entity)
+    }
+
+    #[test]
+    fn steps_report_what_changed() {
+        let (mut world, entity) = counting_world();
+        let debugger = Debugger::new(entity);
+        let executed = debugger.step(&mut world).unwrap();
+        assert_eq!(executed.index, 0);
+        assert!(executed.taken);
+        assert_eq!(executed.changed_registers().collect::<Vec<_>>(), [Register::LongRegister0, Register::ByteRegister0_0]);
+        assert_eq!(executed.to_string(), "   0  add r0, r1  ; taken, r0 = 0x1, r0.b0 = 0x1");
+        assert_eq!(world.entity(entity).unwrap().next_index(), 1);
+        debugger.step(&mut world);
+        let executed = debugger.step(&mut world).unwrap();
+        assert_eq!(executed.index, 2);
+        assert_eq!(executed.to_string(), "   2  move 0  ; taken, moved to 2,1");
+        assert_eq!(world.entity(entity).unwrap().pos(), [2, 1]);
+    }
+
+    #[test]
+    fn resume_stops_before_breakpoints() {
+        let (mut world, entity) = counting_world();
+        let mut debugger = Debugger::new(entity);
+        debugger.add_breakpoint(Breakpoint::Instruction(2));
+        debugger.add_breakpoint(Breakpoint::Instruction(2));
+        assert_eq!(debugger.breakpoints(), [Breakpoint::Instruction(2)]);
+        assert_eq!(debugger.resume(&mut world, 100), Stop::Breakpoint(0));
+        let stopped = world.entity(entity).unwrap();
+        assert_eq!((stopped.next_index(), stopped.inner().get(Register::LongRegister0), stopped.pos()), (2, 2, [1, 1]));
+        // resuming runs the instruction on the breakpoint and goes around the loop
+        assert_eq!(debugger.resume(&mut world, 100), Stop::Breakpoint(0));
+        let stopped = world.entity(entity).unwrap();
+        assert_eq!((stopped.next_index(), stopped.inner().get(Register::LongRegister0), stopped.pos()), (2, 4, [2, 1]));
+        assert_eq!(debugger.resume(&mut world, 3), Stop::Limit);
+        assert_eq!(world.entity(entity).unwrap().next_index(), 1);
+    }
+
+    #[test]
+    fn response_breakpoints_stop_before_their_kind() {
+        let (mut world, entity) = counting_world();
+        let mut debugger = Debugger::new(entity);
+        debugger.add_breakpoint(Breakpoint::Instruction(0));
+        debugger.add_breakpoint(Breakpoint::Response(ResponseKind::Jump));
+        assert_eq!(debugger.remove_breakpoint(0), Some(Breakpoint::Instruction(0)));
+        assert_eq!(debugger.remove_breakpoint(1), None);
+        assert_eq!(debugger.resume(&mut world, 100), Stop::Breakpoint(0));
+        assert_eq!(world.entity(entity).unwrap().next_index(), 3);
+        assert_eq!(world.entity(entity).unwrap().pos(), [2, 1]);
+    }
+
+    #[test]
+    fn watches_report_register_values() {
+        let (mut world, entity) = counting_world();
+        let mut debugger = Debugger::new(entity);
+        debugger.watch(Register::LongRegister0);
+        debugger.watch(Register::ByteRegister1_0);
+        debugger.watch(Register::LongRegister0);
+        debugger.resume(&mut world, 2);
+        assert_eq!(debugger.watched(&world), Some(vec![(Register::LongRegister0, 2), (Register::ByteRegister1_0, 1)]));
+        debugger.unwatch(Register::LongRegister0);
+        assert_eq!(debugger.watched(&world), Some(vec![(Register::ByteRegister1_0, 1)]));
+    }
+
+    #[test]
+    fn removed_entities_stop_the_debugger() {
+        let (mut world, entity) = counting_world();
+        let debugger = Debugger::new(entity);
+        world.remove(1, 1);
+        assert!(debugger.step(&mut world).is_none());
+        assert_eq!(debugger.resume(&mut world, 100), Stop::Gone);
+        assert_eq!(debugger.watched(&world), None);
+    }
+}
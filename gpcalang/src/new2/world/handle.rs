@@ -0,0 +1,170 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// refers to an entity for as long as it lives. Once the entity dies its slot is
+/// reused with a new generation, so an old handle never refers to a newer entity.
+/// Generation 0 is never handed out, the default handle refers to nothing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct EntityHandle {
+    pub index: u32,
+    pub generation: u32,
+}
+/// `index:generation`, e.g. `12:3`.
+impl Display for EntityHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.index, self.generation)
+    }
+}
+
+/// reads the `index:generation` [`EntityHandle`] prints.
+impl FromStr for EntityHandle {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (index, generation) = s.split_once(':').ok_or_else(|| format!("expected index:generation, found '{s}'"))?;
+        Ok(Self {
+            index: index.parse().map_err(|err| format!("invalid index '{index}': {err}"))?,
+            generation: generation.parse().map_err(|err| format!("invalid generation '{generation}': {err}"))?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Slot {
+    generation: u32,
+    /// position of the entity in the update order, `None` while the slot is free.
+    entity: Option<u32>,
+}
+
+/// maps handles to positions in the update order of the world.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Slots {
+    slots: Vec<Slot>,
+    /// free slots, the most recently freed one is reused first.
+    free: Vec<u32>,
+}
+impl Slots {
+    pub(crate) fn insert(&mut self, entity: usize) -> EntityHandle {
+        let entity = Some(entity as u32);
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.entity = entity;
+                EntityHandle { index, generation: slot.generation }
+            }
+            None => {
+                self.slots.push(Slot { generation: 1, entity });
+                EntityHandle { index: self.slots.len() as u32-1, generation: 1 }
+            }
+        }
+    }
+    /// frees the slot of `handle`, every copy of it stops resolving.
+    pub(crate) fn remove(&mut self, handle: EntityHandle) {
+        if self.get(handle).is_some() {
+            let slot = &mut self.slots[handle.index as usize];
+            slot.generation = slot.generation.wrapping_add(1).max(1);
+            slot.entity = None;
+            self.free.push(handle.index);
+        }
+    }
+    /// position of the entity in the update order.
+    pub(crate) fn get(&self, handle: EntityHandle) -> Option<usize> {
+        let slot = self.slots.get(handle.index as usize)?;
+        if slot.generation != handle.generation {
+            return None;
+        }
+        slot.entity.map(|entity| entity as usize)
+    }
+    /// whether every slot is either free or taken by one of `entities` entities and
+    /// exactly that many are taken. Holds for every world, a corrupt snapshot may
+    /// break it.
+    pub(crate) fn is_consistent(&self, entities: usize) -> bool {
+        let mut free = vec![false; self.slots.len()];
+        for index in self.free.iter().map(|index| *index as usize) {
+            if index >= self.slots.len() || free[index] || self.slots[index].entity.is_some() {
+                return false;
+            }
+            free[index] = true;
+        }
+        let taken = self.slots.iter().zip(free).filter(|(_, free)| !free).map(|(slot, _)| slot);
+        let mut count = 0;
+        for slot in taken {
+            match slot.entity {
+                Some(entity) if (entity as usize) < entities && slot.generation != 0 => count += 1,
+                _ => return false,
+            }
+        }
+        count == entities
+    }
+    /// records that the entity of `handle` moved to `entity` in the update order.
+    pub(crate) fn set(&mut self, handle: EntityHandle, entity: usize) {
+        if self.get(handle).is_some() {
+            self.slots[handle.index as usize].entity = Some(entity as u32);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn freed_slots_are_reused_last_in_first_out() {
+        let mut slots = Slots::default();
+        let handles = (0..4).map(|entity| slots.insert(entity)).collect::<Vec<_>>();
+        assert_eq!(handles.iter().map(|handle| (handle.index, handle.generation)).collect::<Vec<_>>(), [(0, 1), (1, 1), (2, 1), (3, 1)]);
+        slots.remove(handles[1]);
+        slots.remove(handles[3]);
+        assert_eq!(slots.insert(5), EntityHandle { index: 3, generation: 2 });
+        assert_eq!(slots.insert(6), EntityHandle { index: 1, generation: 2 });
+        assert_eq!(slots.insert(7), EntityHandle { index: 4, generation: 1 });
+    }
+    #[test]
+    fn stale_handles_do_not_resolve() {
+        let mut slots = Slots::default();
+        let stale = slots.insert(0);
+        slots.remove(stale);
+        let fresh = slots.insert(1);
+        assert_eq!(fresh.index, stale.index);
+        assert_eq!((slots.get(stale), slots.get(fresh)), (None, Some(1)));
+        // neither moving nor removing through a stale handle touches the new entity
+        slots.set(stale, 5);
+        slots.remove(stale);
+        assert_eq!(slots.get(fresh), Some(1));
+        slots.set(fresh, 0);
+        assert_eq!(slots.get(fresh), Some(0));
+        assert_eq!(slots.get(EntityHandle::default()), None);
+        assert_eq!(slots.get(EntityHandle { index: 9, generation: 1 }), None);
+    }
+    #[test]
+    fn generations_wrap_past_zero() {
+        let mut slots = Slots::default();
+        let handle = slots.insert(0);
+        slots.slots[0].generation = u32::MAX;
+        let last = EntityHandle { generation: u32::MAX, ..handle };
+        slots.remove(last);
+        let wrapped = slots.insert(0);
+        assert_eq!(wrapped, EntityHandle { index: 0, generation: 1 });
+        assert_eq!(slots.get(last), None);
+        assert_eq!(slots.get(EntityHandle { index: 0, generation: 0 }), None);
+    }
+    #[test]
+    fn corrupt_slot_tables_are_inconsistent() {
+        let mut slots = Slots::default();
+        let handles = (0..3).map(|entity| slots.insert(entity)).collect::<Vec<_>>();
+        slots.remove(handles[2]);
+        assert!(slots.is_consistent(2));
+        assert!(!slots.is_consistent(3));
+        let corrupt = |change: fn(&mut Slots)| {
+            let mut slots = slots.clone();
+            change(&mut slots);
+            slots.is_consistent(2)
+        };
+        assert!(!corrupt(|slots| slots.free.push(2)), "freed twice");
+        assert!(!corrupt(|slots| slots.free.push(7)), "free slot past the end");
+        assert!(!corrupt(|slots| slots.free.push(0)), "free slot that is taken");
+        assert!(!corrupt(|slots| slots.slots[1].entity = Some(2)), "entity past the end");
+        assert!(!corrupt(|slots| slots.slots[1].generation = 0), "generation 0");
+        assert!(!corrupt(|slots| slots.free.clear()), "free slot that is not listed");
+    }
+}
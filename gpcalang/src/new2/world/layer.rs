@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+
+use super::Topology;
+
+/// how a layer is created and how it changes every step.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayerRules {
+    pub name: String,
+    /// value of every cell when the layer is added.
+    pub initial: u32,
+    /// regrowth stops at this value, diffusion can still push a cell past it.
+    pub capacity: u32,
+    /// added to every cell each step.
+    pub regrowth: u32,
+    /// fraction of every cell that is lost each step.
+    pub decay: f64,
+    /// fraction of every cell that spreads evenly to its four direct neighbors each
+    /// step. The share that would cross an edge that does not wrap stays in the cell.
+    pub diffusion: f64,
+    /// the most a single harvest takes from a cell.
+    pub harvest: u32,
+}
+impl Default for LayerRules {
+    fn default() -> Self {
+        Self { name: String::new(), initial: 0, capacity: u32::MAX, regrowth: 0, decay: 0.0, diffusion: 0.0, harvest: 64 }
+    }
+}
+impl LayerRules {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), ..Default::default() }
+    }
+}
+
+/// how the signal entities deposit fades and spreads every step.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SignalRules {
+    /// fraction of the signal in every cell that is lost each step.
+    pub decay: f64,
+    /// fraction of the signal in every cell that spreads to its four direct neighbors
+    /// each step.
+    pub diffusion: f64,
+}
+impl Default for SignalRules {
+    fn default() -> Self {
+        Self { decay: 0.1, diffusion: 0.2 }
+    }
+}
+impl SignalRules {
+    pub(crate) fn layer_rules(&self) -> LayerRules {
+        LayerRules { decay: self.decay, diffusion: self.diffusion, harvest: 0, ..LayerRules::new("signal") }
+    }
+}
+
+/// a named value for every cell of the map, e.g. food, light or a toxin.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Layer {
+    pub(crate) rules: LayerRules,
+    pub(crate) cells: Vec<u32>,
+}
+impl Layer {
+    pub(crate) fn new(rules: LayerRules, width: u32, height: u32) -> Self {
+        Self { cells: vec![rules.initial; (width*height) as usize], rules }
+    }
+    pub fn rules(&self) -> &LayerRules {
+        &self.rules
+    }
+    pub fn name(&self) -> &str {
+        &self.rules.name
+    }
+    /// the cells in the same order as the occupancy map, row by row from `y = 0`.
+    pub fn cells(&self) -> &[u32] {
+        &self.cells
+    }
+    /// applies diffusion, then decay, then regrowth. All amounts are rounded down and
+    /// a share that would push a neighbor past `u32::MAX` stays in its cell, so
+    /// diffusion never creates or destroys anything.
+    pub(crate) fn update(&mut self, topology: Topology, width: u32, height: u32) {
+        let linear = |[x, y]: [u32; 2]| (x+y*width) as usize;
+        if self.rules.diffusion > 0.0 {
+            let mut next = self.cells.clone();
+            for y in 0..height {
+                for x in 0..width {
+                    let idx = linear([x, y]);
+                    let share = (self.cells[idx] as f64*self.rules.diffusion/4.0) as u32;
+                    if share == 0 {
+                        continue;
+                    }
+                    for delta in [(1, 0), (0, 1), (-1, 0), (0, -1)] {
+                        if let Some(neighbor) = topology.offset([x, y], delta, width, height) {
+                            let neighbor = linear(neighbor);
+                            let moved = share.min(u32::MAX-next[neighbor]);
+                            next[idx] -= moved;
+                            next[neighbor] += moved;
+                        }
+                    }
+                }
+            }
+            self.cells = next;
+        }
+        if self.rules.decay > 0.0 {
+            for cell in self.cells.iter_mut() {
+                *cell = (*cell as f64*(1.0 - self.rules.decay)) as u32;
+            }
+        }
+        if self.rules.regrowth > 0 {
+            for cell in self.cells.iter_mut() {
+                if *cell < self.rules.capacity {
+                    *cell = cell.saturating_add(self.rules.regrowth).min(self.rules.capacity);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use crate::new2::entity::{asm::assemble, GPCAEntity};
+    use crate::new2::world::World;
+
+    fn total(layer: &Layer) -> u64 {
+        layer.cells().iter().map(|cell| *cell as u64).sum()
+    }
+
+    #[test]
+    fn regrowth_stops_at_capacity() {
+        let mut layer = Layer::new(LayerRules { capacity: 10, regrowth: 3, ..LayerRules::new("food") }, 2, 1);
+        layer.cells[1] = 15;
+        for expected in [3, 6, 9, 10, 10] {
+            layer.update(Topology::Bounded, 2, 1);
+            assert_eq!(layer.cells(), [expected, 15]);
+        }
+    }
+    #[test]
+    fn diffusion_conserves_the_total() {
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(6);
+        for topology in [Topology::Bounded, Topology::Toroidal, Topology::Cylinder] {
+            for (width, height) in [(1, 1), (2, 1), (5, 4)] {
+                for diffusion in [0.1, 0.5, 1.0] {
+                    let mut layer = Layer::new(LayerRules { diffusion, ..LayerRules::new("food") }, width, height);
+                    layer.cells.iter_mut().for_each(|cell| *cell = rng.gen_range(0..100_000));
+                    let before = total(&layer);
+                    for _ in 0..10 {
+                        layer.update(topology, width, height);
+                        assert_eq!(total(&layer), before, "{diffusion} on a {topology} {width}x{height} map");
+                    }
+                }
+            }
+        }
+    }
+    #[test]
+    fn diffusion_conserves_full_cells() {
+        let mut layer = Layer::new(LayerRules { initial: u32::MAX-3, diffusion: 1.0, ..LayerRules::new("food") }, 3, 3);
+        layer.cells[4] = u32::MAX;
+        let before = total(&layer);
+        layer.update(Topology::Toroidal, 3, 3);
+        assert_eq!(total(&layer), before);
+    }
+    #[test]
+    fn diffusion_keeps_what_would_cross_a_wall() {
+        let mut layer = Layer::new(LayerRules { diffusion: 1.0, ..LayerRules::new("food") }, 3, 1);
+        layer.cells[0] = 400;
+        layer.update(Topology::Bounded, 3, 1);
+        assert_eq!(layer.cells(), [300, 100, 0]);
+        layer.update(Topology::Toroidal, 3, 1);
+        assert_eq!(layer.cells(), [175, 125, 100]);
+    }
+    #[test]
+    fn harvests_credit_energy() {
+        let mut world = World::new(vec![], 1, 2, 2, true, 0.0, Some(1));
+        world.add_layer(LayerRules { initial: 100, harvest: 30, ..LayerRules::new("food") });
+        world.push_entity(GPCAEntity::new(1, 1, 0, 0, 50, 0, assemble("harvest 0").unwrap()));
+        for (energy, cell) in [(79, 70), (108, 40), (137, 10), (146, 0), (145, 0)] {
+            world.step(|_| {}, |_| {});
+            assert_eq!((world.get_entites()[0].get_energy(), world.layer_value(0, [1, 1])), (energy, cell));
+        }
+        assert_eq!(world.layer_value(0, [0, 0]), 100);
+    }
+}
@@ -0,0 +1,340 @@
+use std::{collections::{BTreeMap, HashSet}, fmt::Write, ops::{AddAssign, Sub}};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Executed, World};
+use crate::new2::entity::bytecode::ResponseKind;
+
+/// running counts of what happened in a world since it was created, see
+/// [`World::tally`]. A [`MetricsCollector`] reports how they changed between two
+/// samples.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tally {
+    /// entities added to the world, the starting population included.
+    pub births: u64,
+    /// entities that ran out of energy.
+    pub starved: u64,
+    /// entities an action ate, see [`super::ActionContext::eat`]. Other removals are
+    /// not counted.
+    pub eaten: u64,
+    /// entities whose code the mutation pipeline changed, see
+    /// [`World::create_entity`].
+    pub mutations: u64,
+    pub usage: Usage,
+}
+impl Sub for Tally {
+    type Output = Tally;
+    fn sub(self, rhs: Tally) -> Tally {
+        Tally {
+            births: self.births-rhs.births,
+            starved: self.starved-rhs.starved,
+            eaten: self.eaten-rhs.eaten,
+            mutations: self.mutations-rhs.mutations,
+            usage: self.usage-rhs.usage,
+        }
+    }
+}
+
+/// instructions entities ran, by what their response does.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    /// instructions whose event was false, so their response did not run.
+    pub skipped: u64,
+    pub nop: u64,
+    pub arithmetic: u64,
+    pub jump: u64,
+    #[serde(rename = "move")]
+    pub movement: u64,
+    pub call: u64,
+    pub harvest: u64,
+    pub signal: u64,
+}
+impl Usage {
+    pub fn count(&mut self, executed: &Executed) {
+        if !executed.taken {
+            self.skipped += 1;
+            return;
+        }
+        *match executed.response.kind() {
+            ResponseKind::Nop => &mut self.nop,
+            ResponseKind::Arithmetic => &mut self.arithmetic,
+            ResponseKind::Jump => &mut self.jump,
+            ResponseKind::Move => &mut self.movement,
+            ResponseKind::Call => &mut self.call,
+            ResponseKind::Harvest => &mut self.harvest,
+            ResponseKind::Signal => &mut self.signal,
+        } += 1;
+    }
+    /// responses of `kind` that ran.
+    pub fn of(&self, kind: ResponseKind) -> u64 {
+        match kind {
+            ResponseKind::Nop => self.nop,
+            ResponseKind::Arithmetic => self.arithmetic,
+            ResponseKind::Jump => self.jump,
+            ResponseKind::Move => self.movement,
+            ResponseKind::Call => self.call,
+            ResponseKind::Harvest => self.harvest,
+            ResponseKind::Signal => self.signal,
+        }
+    }
+    /// every instruction, skipped ones included.
+    pub fn total(&self) -> u64 {
+        self.skipped+ResponseKind::ALL.iter().map(|kind| self.of(*kind)).sum::<u64>()
+    }
+}
+impl AddAssign for Usage {
+    fn add_assign(&mut self, rhs: Usage) {
+        self.skipped += rhs.skipped;
+        self.nop += rhs.nop;
+        self.arithmetic += rhs.arithmetic;
+        self.jump += rhs.jump;
+        self.movement += rhs.movement;
+        self.call += rhs.call;
+        self.harvest += rhs.harvest;
+        self.signal += rhs.signal;
+    }
+}
+impl Sub for Usage {
+    type Output = Usage;
+    fn sub(self, rhs: Usage) -> Usage {
+        Usage {
+            skipped: self.skipped-rhs.skipped,
+            nop: self.nop-rhs.nop,
+            arithmetic: self.arithmetic-rhs.arithmetic,
+            jump: self.jump-rhs.jump,
+            movement: self.movement-rhs.movement,
+            call: self.call-rhs.call,
+            harvest: self.harvest-rhs.harvest,
+            signal: self.signal-rhs.signal,
+        }
+    }
+}
+
+/// entities per square block of cells, row by row starting with the block at 0,0.
+/// Blocks on the right and top edge are cut off if the map is not a multiple of the
+/// block size.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Density {
+    /// side of a block in cells.
+    pub block: u32,
+    pub columns: u32,
+    pub rows: u32,
+    pub counts: Vec<u32>,
+}
+impl Density {
+    pub fn new(world: &World, block: u32) -> Self {
+        assert!(block != 0, "density blocks need at least one cell");
+        let (columns, rows) = (world.width().div_ceil(block), world.height().div_ceil(block));
+        let mut counts = vec![0; (columns*rows) as usize];
+        for entity in world.get_entites().iter().filter(|entity| world.is_alive(entity.handle())) {
+            let [x, y] = entity.pos();
+            counts[(x/block+y/block*columns) as usize] += 1;
+        }
+        Self { block, columns, rows, counts }
+    }
+    pub fn at(&self, column: u32, row: u32) -> u32 {
+        self.counts[(column+row*self.columns) as usize]
+    }
+    /// the highest share of occupied cells in any block, cut off blocks only count
+    /// their cells on the map.
+    pub fn peak(&self, width: u32, height: u32) -> f64 {
+        (0..self.rows).flat_map(|row| (0..self.columns).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                let cells = (width-column*self.block).min(self.block)*(height-row*self.block).min(self.block);
+                self.at(column, row) as f64/cells as f64
+            })
+            .fold(0.0, f64::max)
+    }
+}
+
+/// population statistics of a world at one step, see [`MetricsCollector::sample`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Metrics {
+    /// number of times the world was stepped.
+    pub step: u64,
+    pub population: usize,
+    /// entities added since the previous sample.
+    pub births: u64,
+    /// entities that ran out of energy since the previous sample.
+    pub starved: u64,
+    /// entities eaten since the previous sample.
+    pub eaten: u64,
+    /// entities added with mutated code since the previous sample.
+    pub mutations: u64,
+    pub mean_energy: f64,
+    pub min_energy: u32,
+    pub max_energy: u32,
+    /// number of entities by the length of their code.
+    pub genome_lengths: BTreeMap<usize, usize>,
+    /// number of different codes in the population.
+    pub distinct_genomes: usize,
+    /// instructions run since the previous sample.
+    pub usage: Usage,
+    /// share of the cells of the map that are occupied.
+    pub occupancy: f64,
+    /// see [`Density::peak`].
+    pub peak_density: f64,
+    pub density: Density,
+}
+impl Metrics {
+    /// the columns of [`Metrics::csv_row`]. The genome length distribution and the
+    /// density are summarized, [`Metrics::json`] has them in full.
+    pub const CSV_HEADER: &'static str = "step,population,mean_energy,min_energy,max_energy,births,starved,eaten,mutations,\
+        min_genome_len,mean_genome_len,max_genome_len,distinct_genomes,occupancy,peak_density,\
+        skipped,nop,arithmetic,jump,move,call,harvest,signal";
+    pub fn min_genome_len(&self) -> usize {
+        self.genome_lengths.keys().next().copied().unwrap_or_default()
+    }
+    pub fn max_genome_len(&self) -> usize {
+        self.genome_lengths.keys().next_back().copied().unwrap_or_default()
+    }
+    pub fn mean_genome_len(&self) -> f64 {
+        if self.population == 0 {
+            return 0.0;
+        }
+        let total = self.genome_lengths.iter().map(|(len, entities)| len*entities).sum::<usize>();
+        total as f64/self.population as f64
+    }
+    pub fn csv_row(&self) -> String {
+        let mut row = format!(
+            "{},{},{:.2},{},{},{},{},{},{},{},{:.2},{},{},{:.4},{:.4},{}",
+            self.step, self.population, self.mean_energy, self.min_energy, self.max_energy,
+            self.births, self.starved, self.eaten, self.mutations,
+            self.min_genome_len(), self.mean_genome_len(), self.max_genome_len(), self.distinct_genomes,
+            self.occupancy, self.peak_density, self.usage.skipped,
+        );
+        for kind in ResponseKind::ALL {
+            write!(row, ",{}", self.usage.of(kind)).unwrap();
+        }
+        row
+    }
+    /// a single line of JSON, for JSON Lines files.
+    pub fn json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+/// samples [`Metrics`] of a world. Births, deaths and instructions are counted from
+/// one sample to the next, so sampling every step gives them per step and sampling
+/// at an interval gives them per interval.
+#[derive(Clone, Debug)]
+pub struct MetricsCollector {
+    block: u32,
+    last: Tally,
+}
+impl MetricsCollector {
+    /// the first sample counts from the current state of `world`. Density is counted
+    /// in blocks of `block` by `block` cells.
+    pub fn new(world: &World, block: u32) -> Self {
+        assert!(block != 0, "density blocks need at least one cell");
+        Self { block, last: *world.tally() }
+    }
+    pub fn block(&self) -> u32 {
+        self.block
+    }
+    pub fn sample(&mut self, world: &World) -> Metrics {
+        let tally = *world.tally();
+        let since = tally-self.last;
+        self.last = tally;
+        // entities taken off the map are still listed until their next turn
+        let entities = world.get_entites().iter().filter(|entity| world.is_alive(entity.handle())).collect::<Vec<_>>();
+        let energies = entities.iter().map(|entity| entity.get_energy());
+        let total = energies.clone().map(|energy| energy as u64).sum::<u64>();
+        let mut genome_lengths = BTreeMap::new();
+        for entity in entities.iter() {
+            *genome_lengths.entry(entity.code().len()).or_insert(0) += 1;
+        }
+        let density = Density::new(world, self.block);
+        Metrics {
+            step: world.steps(),
+            population: entities.len(),
+            births: since.births,
+            starved: since.starved,
+            eaten: since.eaten,
+            mutations: since.mutations,
+            mean_energy: if entities.is_empty() { 0.0 } else { total as f64/entities.len() as f64 },
+            min_energy: energies.clone().min().unwrap_or_default(),
+            max_energy: energies.max().unwrap_or_default(),
+            genome_lengths,
+            distinct_genomes: entities.iter().map(|entity| entity.code()).collect::<HashSet<_>>().len(),
+            usage: since.usage,
+            occupancy: entities.len() as f64/(world.width() as f64*world.height() as f64),
+            peak_density: density.peak(world.width(), world.height()),
+            density,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new2::entity::GPCAEntity;
+    use crate::new2::world::{ActionContext, EntityHandle, MutationPipeline};
+
+    fn add(world: &mut World, x: u32, y: u32) -> EntityHandle {
+        world.push_entity(GPCAEntity::new(x, y, 0, 0, 10, 0, vec![0; 4]))
+    }
+    fn act(world: &mut World, action: impl FnOnce(&mut ActionContext)) {
+        let mut ctx = ActionContext::new(world, 0);
+        action(&mut ctx);
+        ctx.apply();
+    }
+
+    #[test]
+    fn edge_blocks_only_count_cells_on_the_map() {
+        // 5 by 3 cells in blocks of 2 leaves a column of 2x1 blocks on the right, a
+        // row of 1x2 blocks on top and a single cell in the corner
+        let mut world = World::new(vec![], 16, 5, 3, true, 0.0, Some(1));
+        add(&mut world, 0, 0);
+        let density = Density::new(&world, 2);
+        assert_eq!((density.columns, density.rows, density.counts.len()), (3, 2, 6));
+        assert_eq!(density.peak(5, 3), 0.25);
+        add(&mut world, 0, 2);
+        assert_eq!(Density::new(&world, 2).peak(5, 3), 0.5);
+        add(&mut world, 4, 0);
+        assert_eq!(Density::new(&world, 2).peak(5, 3), 0.5);
+        add(&mut world, 4, 2);
+        let density = Density::new(&world, 2);
+        assert_eq!((density.at(0, 0), density.at(0, 1), density.at(2, 0), density.at(2, 1)), (1, 1, 1, 1));
+        assert_eq!(density.peak(5, 3), 1.0);
+        // a block larger than the map is a single cut off block
+        let density = Density::new(&world, 8);
+        assert_eq!((density.columns, density.rows), (1, 1));
+        assert_eq!(density.peak(5, 3), 4.0/15.0);
+    }
+    #[test]
+    fn samples_count_since_the_previous_one() {
+        let mut world = World::new(vec![], 16, 4, 4, true, 0.0, Some(1));
+        add(&mut world, 0, 0);
+        let mut collector = MetricsCollector::new(&world, 2);
+        let prey = add(&mut world, 1, 0);
+        let removed = add(&mut world, 2, 0);
+        world.set_mutation(MutationPipeline::flip_bit(1.0));
+        world.create_entity(GPCAEntity::new(3, 0, 0, 0, 10, 0, vec![0; 4]));
+        let metrics = collector.sample(&world);
+        assert_eq!((metrics.births, metrics.eaten, metrics.mutations, metrics.population), (3, 0, 1, 4));
+        let before = *world.tally();
+        act(&mut world, |ctx| {
+            ctx.eat(prey);
+            ctx.remove(removed);
+        });
+        let metrics = collector.sample(&world);
+        assert_eq!((metrics.births, metrics.eaten, metrics.mutations, metrics.population), (0, 1, 0, 2));
+        assert_eq!(*world.tally()-before, Tally { eaten: 1, ..Tally::default() });
+        assert_eq!((world.tally().births, world.tally().eaten, world.tally().mutations), (4, 1, 1));
+    }
+    #[test]
+    fn csv_rows_match_the_header() {
+        let mut world = World::new(vec![], 16, 4, 4, true, 0.0, Some(1));
+        let mut collector = MetricsCollector::new(&world, 2);
+        let columns = Metrics::CSV_HEADER.split(',').collect::<Vec<_>>();
+        assert_eq!(collector.sample(&world).csv_row().split(',').count(), columns.len());
+        world.populate(8, 100, 16);
+        for _ in 0..3 {
+            world.step(|_| {}, |_| {});
+        }
+        assert_eq!(collector.sample(&world).csv_row().split(',').count(), columns.len());
+        assert!(columns.iter().all(|column| !column.is_empty() && column.trim() == *column));
+        assert!(columns.ends_with(&ResponseKind::ALL.map(|kind| kind.name())));
+    }
+}
@@ -1,147 +1,597 @@
-use std::{cell::{RefCell, UnsafeCell}, collections::HashMap, rc::Rc, sync::Arc};
-
-use affogato::spatial::morton::MortonU64;
-use rand::Rng;
-
-use super::entity::{Direction, GPCAEntity, GPCAEntityInternal};
-
-type WorldUserFunction = fn(&Arc<GPCAEntity>, &World);
-
-pub struct World {
-    pub(crate) functions: Vec<WorldUserFunction>,
-    entities: Rc<UnsafeCell<Vec<Arc<GPCAEntity>>>>,
-    // pub(crate) map: Rc<RefCell<BooleanMap>>,
-    // pub(crate) map: Rc<RefCell<HashMap<MortonU64, Arc<GPCAEntity>>>>,
-    map: Rc<RefCell<Vec<u32>>>,
-    pseudo: UnsafeCell<rand_pcg::Pcg64>,
-    width: u32, 
-    height: u32,
-    pub(crate) use_energy: bool,
-    mutation_chance: f64,
-}
-
-impl World {
-    pub fn new(functions: Vec<WorldUserFunction>, entity_capacity: usize, width: u32, height: u32, use_energy: bool, mutation_chance: f64, state: Option<u128>) -> World {
-        Self { functions, entities: Rc::new(UnsafeCell::new(Vec::with_capacity(entity_capacity))), map: Rc::new(RefCell::new(vec![0xffffffff; (width*height) as usize])), pseudo: UnsafeCell::new(rand_pcg::Pcg64::new(state.unwrap_or(0xcafef00dd15ea5e5), 0xa02bdbf7bb3c0a7ac28fa16a64abf96)), width, height, use_energy, mutation_chance }
-    }
-    // pub fn surrounding_square_count(&self, x: u32, y: u32) -> usize {
-    //     let map = self.map.borrow();
-    //     map.surrounding_square_count(x, y)
-    // }
-    pub fn surrounding_square_count(&self, x: u32, y: u32) -> usize {
-        let mut count = 0;
-        for y in (y.checked_sub(1).unwrap_or_default())..((y+1).clamp(0, self.height)) {
-            for x in (x.checked_sub(1).unwrap_or_default())..((x+1).clamp(0, self.width)) {
-                count += self.get(x, y) as usize;
-            }
-        }
-        count
-    }
-    pub fn get_entites(&self) -> &Vec<Arc<GPCAEntity>> {
-        unsafe { self.entities.get().as_ref().unwrap() }
-    }
-    pub fn get_entites_mut(&self) -> &mut Vec<Arc<GPCAEntity>> {
-        unsafe { self.entities.get().as_mut().unwrap() }
-    }
-    pub fn push_entity(&self, entity: GPCAEntity) {
-        let entities = self.get_entites_mut();
-        entity.inner_mut().id = entities.len() as u32;
-        let entity = Arc::new(entity);
-        self.set(&entity);
-        entities.push(entity.clone());
-    }
-    pub fn create_entity(&self, mut entity: GPCAEntity) {
-        let pseudo = unsafe { self.pseudo.get().as_mut().unwrap() };
-        if self.mutation_chance != 0.0 {
-            if pseudo.gen_bool(self.mutation_chance) {
-                let code_to_mutate = pseudo.gen_range(0..entity.code.len());
-                let bit_to_mutate = pseudo.gen_range(0..32);
-                entity.code[code_to_mutate] ^= 1<<bit_to_mutate;
-                println!("MUTATED");
-            }
-        }
-        let entities = self.get_entites_mut();
-        entity.inner_mut().id = entities.len() as u32;
-        let entity = Arc::new(entity);
-        self.set(&entity);
-        entities.push(entity.clone());
-    }
-    pub fn step<F, H>(&self, mut clear: F, mut place: H) 
-        where F: FnMut(&GPCAEntity),
-            H: FnMut(&GPCAEntity) {
-        let entities = self.get_entites_mut();
-        let mut i = 0;
-        while i < entities.len() {
-            if self.use_energy {
-                if entities[i].get_energy() == 0 {
-                    self.remove(entities[i].x(), entities[i].y());
-                } else {
-                    entities[i].decrement_energy();
-                }
-            }
-            if entities[i].step(self, &mut clear, &mut place) {
-                if i == (entities.len()-1) {
-                    let _ = entities.pop().unwrap();
-                } else {
-                    let id = entities[i].inner().id;
-                    // self.remove(entities[i].inner().x(), entities[i].inner().y());
-                    entities[i] = entities.pop().unwrap();
-                    entities[i].inner_mut().id = id;
-                    self.set(&entities[i]);
-                }
-                continue;
-            }
-            i += 1;
-        }
-        // for i in 0..entities.len() {
-        // }
-    }
-    pub fn get(&self, x: u32, y: u32) -> bool {
-        let map = self.map.borrow();
-        if x >= self.width || y >= self.height {
-            return true;
-        }
-        map[self.linear(x, y)] != 0xffffffff
-    }
-    pub fn pseudo(&self) -> &mut rand_pcg::Lcg128Xsl64 {
-        unsafe { self.pseudo.get().as_mut().unwrap() }
-    }
-    fn linear(&self, x: u32, y: u32) -> usize {
-        (x+y*self.width) as usize
-    }
-    pub fn get_entity_at_direction(&self, entity: &GPCAEntityInternal, dir: Direction) -> Option<Arc<GPCAEntity>> {
-        let map = self.map.borrow();
-        let mut pos = entity.pos;
-        dir.perform_direction(&mut pos, self.width, self.height);
-        
-        let idx = map.get(self.linear(pos[0], pos[1])).cloned()?;
-        if idx == 0xffffffff {
-            None
-        } else {
-            let entities = self.get_entites();
-            Some(entities[idx as usize].clone())
-        }
-    }
-    pub fn set(&self, entity: &Arc<GPCAEntity>) {
-        assert!(entity.inner().pos[0] < self.width && entity.inner().pos[0] < self.height, "x and y can not exceed width and height respectively");
-        let mut map = self.map.borrow_mut();
-
-        map[self.linear(entity.inner().pos[0], entity.inner().pos[1])] = entity.inner().id;
-    }
-    pub fn remove(&self, x: u32, y: u32) {
-        // let mut map = self.map.borrow_mut();
-        // map.set(x, y, active);
-        let mut map = self.map.borrow_mut();
-        map[self.linear(x, y)] = 0xffffffff;
-    }
-    pub fn push(&self, entity: GPCAEntity) {
-        let entities = self.get_entites_mut();
-        entities.push(entity.into());
-    }
-    pub fn width(&self) -> u32 {
-        self.width
-    }
-    pub fn height(&self) -> u32 {
-        self.height
-    }
-}
\ No newline at end of file
+use std::sync::Mutex;
+
+use rand::Rng;
+
+use super::entity::{Direction, Effect, EventResponse, GPCAEntity, GPCAEntityInternal, Program};
+
+pub mod config;
+pub mod actions;
+mod snapshot;
+mod replay;
+mod topology;
+mod neighborhood;
+mod layer;
+mod context;
+mod handle;
+mod parallel;
+mod schedule;
+mod cost;
+mod debug;
+mod trace;
+mod mutation;
+mod crossover;
+mod metrics;
+
+pub use snapshot::{SnapshotError, SNAPSHOT_VERSION};
+pub use replay::{Divergence, DivergenceKind, ReplayError, ReplayLog, StepRecord, REPLAY_VERSION};
+pub use topology::Topology;
+pub use neighborhood::{Neighborhood, NeighborhoodShape};
+pub use layer::{Layer, LayerRules, SignalRules};
+pub use context::{ActionContext, Named, WorldAction};
+pub use handle::EntityHandle;
+pub use parallel::StepMode;
+pub use schedule::Schedule;
+pub use cost::{CostModel, EventCosts, ResponseCosts};
+pub use debug::{Breakpoint, Debugger, Executed, Stop};
+pub use trace::{Compact, CompactRecord, JsonLines, RingBuffer, TraceRecord, TraceSink, TRACE_VERSION};
+pub use mutation::{Mutation, MutationOperator, MutationPipeline, MutationStage};
+pub use crossover::{Crossover, CrossoverOperator};
+pub use metrics::{Density, Metrics, MetricsCollector, Tally, Usage};
+pub(crate) use handle::Slots;
+
+/// a plain function as a [`WorldAction`].
+pub type WorldUserFunction = fn(&mut ActionContext);
+
+pub struct World {
+    pub(crate) functions: Vec<Box<dyn WorldAction>>,
+    /// every living entity in update order.
+    entities: Vec<GPCAEntity>,
+    slots: Slots,
+    map: Vec<Option<EntityHandle>>,
+    pseudo: rand_pcg::Pcg64,
+    width: u32, 
+    height: u32,
+    pub(crate) use_energy: bool,
+    mutation: MutationPipeline,
+    crossover: CrossoverOperator,
+    topology: Topology,
+    neighborhoods: Vec<Neighborhood>,
+    layers: Vec<Layer>,
+    signal: Layer,
+    schedule: Schedule,
+    costs: CostModel,
+    step_mode: StepMode,
+    /// threads a parallel step evaluates entities on, not part of snapshots since it
+    /// does not change the result.
+    threads: usize,
+    /// number of times the world was stepped.
+    steps: u64,
+    /// not part of snapshots, a loaded world counts from zero.
+    tally: Tally,
+    recorder: Option<ReplayLog>,
+    /// only used through `&mut self`, the mutex keeps the world `Sync` for parallel
+    /// steps.
+    tracer: Option<Mutex<Box<dyn TraceSink>>>,
+}
+
+impl World {
+    /// the rng state [`World::new`] picks if it is given none.
+    pub(crate) const DEFAULT_STATE: u128 = 0xcafef00dd15ea5e5;
+    pub fn new(functions: Vec<Box<dyn WorldAction>>, entity_capacity: usize, width: u32, height: u32, use_energy: bool, mutation_chance: f64, state: Option<u128>) -> World {
+        Self { functions, entities: Vec::with_capacity(entity_capacity), slots: Slots::default(), map: vec![None; (width*height) as usize], pseudo: rand_pcg::Pcg64::new(state.unwrap_or(Self::DEFAULT_STATE), 0xa02bdbf7bb3c0a7ac28fa16a64abf96), width, height, use_energy, mutation: MutationPipeline::flip_bit(mutation_chance), crossover: CrossoverOperator::Alternating, topology: Topology::Bounded, neighborhoods: vec![Neighborhood::default()], layers: vec![], signal: Layer::new(SignalRules::default().layer_rules(), width, height), schedule: Schedule::Sequential, costs: CostModel::flat(), step_mode: StepMode::Sequential, threads: Self::default_threads(), steps: 0, tally: Tally::default(), recorder: None, tracer: None }
+    }
+    // pub fn surrounding_square_count(&self, x: u32, y: u32) -> usize {
+    //     let map = self.map.borrow();
+    //     map.surrounding_square_count(x, y)
+    // }
+    /// occupied cells in the first neighborhood of the world around `x`, `y`.
+    pub fn surrounding_square_count(&self, x: u32, y: u32) -> usize {
+        self.neighbor_count([x, y], 0)
+    }
+    /// occupied cells around `pos` in the neighborhood a surrounding square event
+    /// selected.
+    pub fn neighbor_count(&self, pos: [u32; 2], selector: u8) -> usize {
+        self.count_neighbors(pos, self.neighborhood(selector))
+    }
+    /// occupied cells around `pos` in `neighborhood`. Cells past an edge that does not
+    /// wrap are not counted, and a cell the neighborhood reaches from more than one
+    /// side of a small wrapping map is counted once.
+    pub fn count_neighbors(&self, pos: [u32; 2], neighborhood: &Neighborhood) -> usize {
+        let cells = neighborhood.offsets().filter_map(|delta| self.topology.offset(pos, delta, self.width, self.height));
+        let span = 2*neighborhood.radius as u64+1;
+        let (wrap_x, wrap_y) = self.topology.wraps();
+        if (wrap_x && span > self.width as u64) || (wrap_y && span > self.height as u64) {
+            let mut cells = cells.filter(|cell| neighborhood.include_self || *cell != pos).collect::<Vec<_>>();
+            cells.sort_unstable();
+            cells.dedup();
+            cells.into_iter().filter(|[x, y]| self.get(*x, *y)).count()
+        } else {
+            cells.filter(|[x, y]| self.get(*x, *y)).count()
+        }
+    }
+    pub fn neighborhoods(&self) -> &[Neighborhood] {
+        &self.neighborhoods
+    }
+    /// the neighborhood for an event selector, selectors past the end of the table
+    /// wrap around so every event counts in some neighborhood.
+    pub fn neighborhood(&self, selector: u8) -> &Neighborhood {
+        &self.neighborhoods[selector as usize%self.neighborhoods.len()]
+    }
+    pub fn set_neighborhoods(&mut self, neighborhoods: Vec<Neighborhood>) {
+        assert!(!neighborhoods.is_empty(), "the world needs at least one neighborhood");
+        self.neighborhoods = neighborhoods;
+    }
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+    pub fn schedule(&self) -> Schedule {
+        self.schedule
+    }
+    pub fn set_schedule(&mut self, schedule: Schedule) {
+        self.schedule = schedule;
+    }
+    pub fn costs(&self) -> &CostModel {
+        &self.costs
+    }
+    pub fn set_costs(&mut self, costs: CostModel) {
+        assert!(costs.budget != 0, "entities need a budget of at least one instruction");
+        self.costs = costs;
+    }
+    pub fn mutation(&self) -> &MutationPipeline {
+        &self.mutation
+    }
+    /// replaces the single bit flip `mutation_chance` of [`World::new`] sets up.
+    pub fn set_mutation(&mut self, mutation: MutationPipeline) {
+        if let Err(err) = mutation.validate() {
+            panic!("invalid mutation pipeline: {err}");
+        }
+        self.mutation = mutation;
+    }
+    /// the actions entities call, in the order `Call` selects them.
+    pub fn functions(&self) -> &[Box<dyn WorldAction>] {
+        &self.functions
+    }
+    pub fn crossover(&self) -> CrossoverOperator {
+        self.crossover
+    }
+    pub fn set_crossover(&mut self, crossover: CrossoverOperator) {
+        self.crossover = crossover;
+    }
+    /// number of times the world was stepped.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+    /// births, deaths and instructions since the world was created, see
+    /// [`MetricsCollector`].
+    pub fn tally(&self) -> &Tally {
+        &self.tally
+    }
+    pub fn step_mode(&self) -> StepMode {
+        self.step_mode
+    }
+    pub fn set_step_mode(&mut self, step_mode: StepMode) {
+        self.step_mode = step_mode;
+    }
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+    /// threads a [`StepMode::Parallel`] step uses, defaults to the available
+    /// parallelism of the machine.
+    pub fn set_threads(&mut self, threads: usize) {
+        assert!(threads != 0, "the world needs at least one thread");
+        self.threads = threads;
+    }
+    pub(crate) fn default_threads() -> usize {
+        std::thread::available_parallelism().map(|threads| threads.get()).unwrap_or(1)
+    }
+    /// the cell next to `pos` in direction `dir`, or `None` if the step would cross an
+    /// edge of the map that does not wrap.
+    pub fn position_at_direction(&self, pos: [u32; 2], dir: Direction) -> Option<[u32; 2]> {
+        self.topology.offset(pos, dir.delta(), self.width, self.height)
+    }
+    /// adds a layer with every cell set to [`LayerRules::initial`] and returns its
+    /// index. Layer events and harvests select layers in the order they were added.
+    pub fn add_layer(&mut self, rules: LayerRules) -> usize {
+        self.layers.push(Layer::new(rules, self.width, self.height));
+        self.layers.len()-1
+    }
+    pub fn layer_count(&self) -> usize {
+        self.layers.len()
+    }
+    pub fn layer_index(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.name() == name)
+    }
+    pub fn layer(&self, layer: usize) -> Option<&Layer> {
+        self.layers.get(layer)
+    }
+    /// the layer an event or harvest selects, selectors past the last layer wrap around.
+    pub(crate) fn select_layer(&self, selector: u8) -> Option<usize> {
+        let count = self.layer_count();
+        (count != 0).then(|| selector as usize%count)
+    }
+    /// value of the layer at `pos`, 0 if there is no such layer.
+    pub fn layer_value(&self, layer: usize, pos: [u32; 2]) -> u32 {
+        let idx = self.linear(pos[0], pos[1]);
+        self.layers.get(layer).map(|layer| layer.cells[idx]).unwrap_or_default()
+    }
+    pub fn set_layer_value(&mut self, layer: usize, pos: [u32; 2], value: u32) {
+        let idx = self.linear(pos[0], pos[1]);
+        self.layers[layer].cells[idx] = value;
+    }
+    /// takes up to [`LayerRules::harvest`] from the layer at `pos` and returns how much
+    /// was taken.
+    pub fn harvest(&mut self, layer: usize, pos: [u32; 2]) -> u32 {
+        let idx = self.linear(pos[0], pos[1]);
+        let Some(layer) = self.layers.get_mut(layer) else {
+            return 0;
+        };
+        let taken = layer.cells[idx].min(layer.rules.harvest);
+        layer.cells[idx] -= taken;
+        taken
+    }
+    fn update_layers(&mut self) {
+        for layer in self.layers.iter_mut() {
+            layer.update(self.topology, self.width, self.height);
+        }
+        self.signal.update(self.topology, self.width, self.height);
+    }
+    /// the layer entities write with [`crate::new2::entity::bytecode::Response::Signal`].
+    pub fn signal_layer(&self) -> &Layer {
+        &self.signal
+    }
+    /// replaces the decay and diffusion of the signal, the current signal is kept.
+    pub fn set_signal_rules(&mut self, rules: SignalRules) {
+        self.signal.rules = rules.layer_rules();
+    }
+    pub fn signal(&self, pos: [u32; 2]) -> u32 {
+        let idx = self.linear(pos[0], pos[1]);
+        self.signal.cells[idx]
+    }
+    pub fn deposit_signal(&mut self, pos: [u32; 2], value: u32) {
+        let idx = self.linear(pos[0], pos[1]);
+        self.signal.cells[idx] = self.signal.cells[idx].saturating_add(value);
+    }
+    pub fn get_entites(&self) -> &Vec<GPCAEntity> {
+        &self.entities
+    }
+    /// the entity `handle` refers to, `None` once it died or was removed from the map.
+    pub fn entity(&self, handle: EntityHandle) -> Option<&GPCAEntity> {
+        let entity = &self.entities[self.slots.get(handle)?];
+        let [x, y] = entity.pos();
+        (self.map[self.linear(x, y)] == Some(handle)).then_some(entity)
+    }
+    pub(crate) fn entity_mut(&mut self, handle: EntityHandle) -> Option<&mut GPCAEntity> {
+        self.entity(handle)?;
+        let idx = self.slots.get(handle)?;
+        Some(&mut self.entities[idx])
+    }
+    pub fn is_alive(&self, handle: EntityHandle) -> bool {
+        self.entity(handle).is_some()
+    }
+    /// adds `entity` at the end of the update order and returns its handle.
+    pub fn push_entity(&mut self, mut entity: GPCAEntity) -> EntityHandle {
+        let handle = self.slots.insert(self.entities.len());
+        entity.inner_mut().handle = handle;
+        self.set(&entity);
+        self.entities.push(entity);
+        self.tally.births += 1;
+        handle
+    }
+    /// like [`World::push_entity`] but the code goes through the mutation pipeline
+    /// first, see [`World::set_mutation`].
+    pub fn create_entity(&mut self, mut entity: GPCAEntity) -> EntityHandle {
+        if let Some(code) = self.mutation.apply(entity.code(), &mut self.pseudo) {
+            entity.set_code(code);
+            self.tally.mutations += 1;
+        }
+        self.push_entity(entity)
+    }
+    /// places `entity_count` entities with random code, registers and color on
+    /// unoccupied cells. The rng is drawn from in the same order as the graphical
+    /// test harness so a seed produces the same starting population in both.
+    pub fn populate(&mut self, entity_count: usize, energy: u32, code_len: u32) {
+        for _ in 0..entity_count {
+            if self.entities.len() >= (self.width*self.height) as usize {
+                break;
+            }
+            let (mut x, mut y);
+            while {
+                x = self.pseudo.gen_range(0..self.width);
+                y = self.pseudo.gen_range(0..self.height);
+                self.get(x, y)
+            } {}
+            let code = (0..code_len).map(|_| self.pseudo.gen_range(0..u32::MAX)).collect::<Vec<_>>();
+            let color = self.pseudo.gen_range(0x77777777..u32::MAX);
+            let reg0 = self.pseudo.gen_range(0..u64::MAX);
+            let reg1 = self.pseudo.gen_range(0..u64::MAX);
+            self.push_entity(GPCAEntity::new(x, y, reg0, reg1, energy, color, code));
+        }
+    }
+    /// [`clear`] when an entity is deleted or moved, this function will be used to
+    /// clear the current spot.
+    /// 
+    /// [`place`] when an entity is moved this function will be used to place the next
+    /// spot.
+    pub fn step<F, H>(&mut self, mut clear: F, mut place: H) 
+        where F: FnMut(&GPCAEntity),
+            H: FnMut(&GPCAEntity) {
+        self.schedule_entities();
+        match self.step_mode {
+            StepMode::Sequential => self.step_sequential(&mut clear, &mut place),
+            StepMode::Parallel => self.step_parallel(&mut clear, &mut place),
+        }
+        self.update_layers();
+        self.steps += 1;
+        self.record_step();
+    }
+    fn step_sequential<F, H>(&mut self, clear: &mut F, place: &mut H)
+        where F: FnMut(&GPCAEntity),
+            H: FnMut(&GPCAEntity) {
+        let mut i = 0;
+        while i < self.entities.len() {
+            if self.use_energy {
+                if self.entities[i].get_energy() == 0 {
+                    // an entity already taken off the map may have been replaced
+                    if self.is_alive(self.entities[i].handle()) {
+                        self.remove(self.entities[i].x(), self.entities[i].y());
+                        self.tally.starved += 1;
+                    }
+                } else {
+                    self.entities[i].inner_mut().spend_energy(self.costs.tick);
+                }
+            }
+            if self.step_entity(i, clear, place) {
+                self.slots.remove(self.entities[i].handle());
+                self.entities.swap_remove(i);
+                if let Some(entity) = self.entities.get(i) {
+                    self.slots.set(entity.handle(), i);
+                }
+                continue;
+            }
+            i += 1;
+        }
+    }
+    /// runs the turn of the entity at `idx` and returns whether it lost its cell and
+    /// has to be dropped.
+    fn step_entity<F, H>(&mut self, idx: usize, clear: &mut F, place: &mut H) -> bool
+        where F: FnMut(&GPCAEntity),
+            H: FnMut(&GPCAEntity) {
+        if !self.is_alive(self.entities[idx].handle()) {
+            clear(&self.entities[idx]);
+            return true;
+        }
+        let mut entity = *self.entities[idx].inner();
+        let mut executed = vec![];
+        let mut usage = Usage::default();
+        let effect = if self.is_tracing() {
+            self.turn(&mut entity, self.entities[idx].program(), |instruction| {
+                usage.count(&instruction);
+                executed.push(instruction);
+            })
+        } else {
+            self.turn(&mut entity, self.entities[idx].program(), |instruction| usage.count(&instruction))
+        };
+        *self.entities[idx].inner_mut() = entity;
+        self.tally.usage += usage;
+        self.perform(idx, effect, clear, place);
+        self.trace(idx, executed);
+        false
+    }
+    /// runs instructions of `program` on `entity` until its turn ends, see
+    /// [`CostModel::budget`], and returns what is left for the world to do. `trace`
+    /// sees every instruction before the world carried out its effect, the check for
+    /// whether the world traces is made once per turn so worlds that do not trace do
+    /// not pay for it per instruction.
+    fn turn(&self, entity: &mut GPCAEntityInternal, program: &Program, mut trace: impl FnMut(Executed)) -> Effect {
+        for instruction in 0..self.costs.budget {
+            if instruction != 0 && self.use_energy && entity.get_energy() == 0 {
+                break;
+            }
+            let before = *entity;
+            let (event_response, taken, effect) = self.execute(entity, program);
+            trace(Executed::new(before, *entity, program, event_response, taken, effect));
+            if effect != Effect::None {
+                return effect;
+            }
+        }
+        Effect::None
+    }
+    /// runs the next instruction of `program` on `entity` and pays for it. Returns the
+    /// instruction, whether its event was true and what is left for the world to do.
+    fn execute(&self, entity: &mut GPCAEntityInternal, program: &Program) -> (EventResponse, bool, Effect) {
+        let event_response = entity.fetch(program.decoded());
+        let mut cost = self.costs.events.of(&event_response.event);
+        let taken = entity.handle_event(event_response.event, self);
+        let effect = if taken {
+            cost = cost.saturating_add(self.costs.responses.of(&event_response.response));
+            entity.handle_response(event_response.response, program.len())
+        } else {
+            Effect::None
+        };
+        if self.use_energy {
+            entity.spend_energy(cost);
+        }
+        (event_response, taken, effect)
+    }
+    /// carries out what is left of a response of the entity at `idx`.
+    fn perform<F, H>(&mut self, idx: usize, effect: Effect, clear: &mut F, place: &mut H)
+        where F: FnMut(&GPCAEntity),
+            H: FnMut(&GPCAEntity) {
+        match effect {
+            Effect::None => {}
+            Effect::Move(step) => {
+                clear(&self.entities[idx]);
+                self.move_entity(idx, step);
+                place(&self.entities[idx]);
+            }
+            Effect::Harvest(selector) => {
+                if let Some(layer) = self.select_layer(selector) {
+                    let harvested = self.harvest(layer, self.entities[idx].pos());
+                    let entity = &mut self.entities[idx];
+                    entity.set_energy(entity.get_energy().saturating_add(harvested));
+                }
+            }
+            Effect::Signal(value) => {
+                self.deposit_signal(self.entities[idx].pos(), value);
+            }
+            Effect::Call(function) => {
+                if !self.functions.is_empty() {
+                    let mut functions = std::mem::take(&mut self.functions);
+                    let function = function%functions.len();
+                    if self.use_energy {
+                        self.entities[idx].inner_mut().spend_energy(functions[function].cost());
+                    }
+                    let mut context = ActionContext::new(self, idx);
+                    functions[function].run(&mut context);
+                    context.apply();
+                    self.functions = functions;
+                }
+            }
+        }
+    }
+    fn move_entity(&mut self, idx: usize, step: Direction) {
+        let prev = self.entities[idx].pos();
+        let Some(next) = self.position_at_direction(prev, step) else {
+            return;
+        };
+        if !self.get(next[0], next[1]) { // space is not occupied
+            self.entities[idx].inner_mut().pos = next;
+            self.remove(prev[0], prev[1]);
+            let cell = self.linear(next[0], next[1]);
+            self.map[cell] = Some(self.entities[idx].handle());
+        }
+    }
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        if x >= self.width || y >= self.height {
+            return true;
+        }
+        self.map[self.linear(x, y)].is_some()
+    }
+    pub fn pseudo(&mut self) -> &mut rand_pcg::Lcg128Xsl64 {
+        &mut self.pseudo
+    }
+    fn linear(&self, x: u32, y: u32) -> usize {
+        (x+y*self.width) as usize
+    }
+    pub fn get_entity_at_direction(&self, entity: &GPCAEntityInternal, dir: Direction) -> Option<&GPCAEntity> {
+        let pos = self.position_at_direction(entity.pos, dir)?;
+        self.entity_at(pos)
+    }
+    pub fn entity_at(&self, pos: [u32; 2]) -> Option<&GPCAEntity> {
+        if pos[0] >= self.width || pos[1] >= self.height {
+            return None;
+        }
+        self.entity(self.map[self.linear(pos[0], pos[1])]?)
+    }
+    pub fn set(&mut self, entity: &GPCAEntity) {
+        assert!(entity.inner().pos[0] < self.width && entity.inner().pos[1] < self.height, "x and y can not exceed width and height respectively");
+        let cell = self.linear(entity.inner().pos[0], entity.inner().pos[1]);
+        self.map[cell] = Some(entity.handle());
+    }
+    pub fn remove(&mut self, x: u32, y: u32) {
+        let cell = self.linear(x, y);
+        self.map[cell] = None;
+    }
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new2::entity::asm::assemble;
+
+    #[test]
+    fn starved_entities_do_not_clear_their_successor() {
+        for step_mode in [StepMode::Sequential, StepMode::Parallel] {
+            let mut world = World::new(vec![], 4, 4, 4, true, 0.0, Some(1));
+            world.set_step_mode(step_mode);
+            let starved = world.push_entity(GPCAEntity::new(1, 1, 0, 0, 0, 0, vec![0]));
+            world.remove(1, 1);
+            let successor = world.push_entity(GPCAEntity::new(1, 1, 0, 0, 10, 0, vec![0]));
+            world.step(|_| {}, |_| {});
+            assert!(!world.is_alive(starved), "{step_mode}");
+            assert!(world.is_alive(successor), "{step_mode}");
+            assert_eq!(world.get_entites().len(), 1, "{step_mode}");
+            assert_eq!(world.tally().starved, 0, "{step_mode}");
+        }
+    }
+
+    #[test]
+    fn signals_decay_every_step() {
+        let mut world = World::new(vec![], 0, 3, 3, true, 0.0, Some(1));
+        world.set_signal_rules(SignalRules { decay: 0.5, diffusion: 0.0 });
+        world.deposit_signal([1, 1], 1000);
+        for expected in [500, 250, 125, 62] {
+            world.step(|_| {}, |_| {});
+            assert_eq!(world.signal([1, 1]), expected);
+        }
+    }
+
+    #[test]
+    fn signals_spread_to_direct_neighbors() {
+        let mut world = World::new(vec![], 0, 5, 5, true, 0.0, Some(1));
+        world.set_signal_rules(SignalRules { decay: 0.0, diffusion: 0.4 });
+        world.deposit_signal([2, 2], 1000);
+        world.step(|_| {}, |_| {});
+        assert_eq!(world.signal([2, 2]), 600);
+        for pos in [[1, 2], [3, 2], [2, 1], [2, 3]] {
+            assert_eq!(world.signal(pos), 100, "{pos:?}");
+        }
+        assert_eq!(world.signal([1, 1]), 0);
+        assert_eq!(world.signal_layer().cells().iter().sum::<u32>(), 1000);
+    }
+
+    #[test]
+    fn signal_events_see_emitted_signals() {
+        for (emitted, moves) in [(25, true), (20, true), (19, false)] {
+            let mut world = World::new(vec![], 2, 5, 5, true, 0.0, Some(1));
+            let emit = assemble("signal r0").unwrap();
+            let listen = assemble("if signal.left >= 20 : move 1").unwrap();
+            world.push_entity(GPCAEntity::new(1, 2, emitted, 0, 10, 0, emit));
+            let observer = world.push_entity(GPCAEntity::new(2, 2, 0, 0, 10, 0, listen));
+            world.step(|_| {}, |_| {});
+            assert_eq!(world.entity(observer).unwrap().pos() == [3, 3], moves, "emitting {emitted}");
+            // the layers update after the turn, so the deposit has already decayed
+            assert!((1..emitted as u32).contains(&world.signal([1, 2])));
+        }
+    }
+
+    #[test]
+    fn changed_code_runs_on_the_next_turn() {
+        let mut world = World::new(vec![], 1, 5, 5, true, 0.0, Some(1));
+        let entity = world.push_entity(GPCAEntity::new(2, 2, 0, 0, 100, 0, assemble("nop").unwrap()));
+        world.step(|_| {}, |_| {});
+        assert_eq!(world.entity(entity).unwrap().pos(), [2, 2]);
+        world.entities[0].set_word(0, assemble("move 0").unwrap()[0]);
+        world.step(|_| {}, |_| {});
+        assert_eq!(world.entity(entity).unwrap().pos(), [3, 2]);
+        world.entities[0].set_code(assemble("move 2").unwrap());
+        world.step(|_| {}, |_| {});
+        assert_eq!(world.entity(entity).unwrap().pos(), [3, 3]);
+    }
+
+    #[test]
+    fn mutated_code_runs_as_mutated() {
+        let mut world = World::new(vec![], 1, 5, 5, true, 0.0, Some(1));
+        world.set_mutation(MutationPipeline::flip_bit(1.0));
+        let code = vec![0; 8];
+        let entity = world.create_entity(GPCAEntity::new(2, 2, 0, 0, 100, 0, code.clone()));
+        let mutated = world.entity(entity).unwrap().code().to_vec();
+        assert_ne!(mutated, code);
+        for _ in 0..mutated.len() {
+            let Some(executed) = world.step_instruction(entity) else {
+                break;
+            };
+            assert_eq!(executed.word, mutated[executed.index]);
+            let decoded = EventResponse::from_word(executed.word);
+            assert_eq!((executed.event, executed.response), (decoded.event, decoded.response));
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use std::ops::RangeInclusive;
+
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+/// changes the code of an entity before it is added to the world, see
+/// [`super::World::create_entity`].
+pub trait Mutation {
+    /// mutates `code` in place. Operators that change the length keep it within
+    /// `lengths` and do nothing if they cannot, code that already is outside of it is
+    /// not moved back in. Every random choice is drawn from `rng`, so the same rng
+    /// state mutates the same code the same way.
+    fn mutate(&self, code: &mut Vec<u32>, lengths: RangeInclusive<usize>, rng: &mut dyn RngCore);
+}
+
+/// the mutation operators a [`MutationPipeline`] is made of.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum MutationOperator {
+    /// flips one bit of one word.
+    FlipBit,
+    /// flips every bit of the code with the chance `rate`.
+    FlipBits { rate: f64 },
+    /// replaces one word with a random one.
+    ReplaceWord,
+    /// inserts a random word at a random position.
+    Insert,
+    /// removes one word.
+    Delete,
+    /// copies a segment of up to `max_segment` words and inserts the copy right
+    /// after the segment.
+    Duplicate { max_segment: usize },
+    /// reverses the order of the words in a segment of up to `max_segment` words.
+    Invert { max_segment: usize },
+    /// moves the ext byte of the event or the response of one word by up to
+    /// `max_delta` in either direction, wrapping around. The opcodes stay the same,
+    /// only the operand changes.
+    ExtByte { max_delta: u8 },
+}
+impl Mutation for MutationOperator {
+    fn mutate(&self, code: &mut Vec<u32>, lengths: RangeInclusive<usize>, rng: &mut dyn RngCore) {
+        let len = code.len();
+        match *self {
+            MutationOperator::FlipBit => {
+                if len == 0 {
+                    return;
+                }
+                let word = rng.gen_range(0..len);
+                let bit = rng.gen_range(0..32);
+                code[word] ^= 1<<bit;
+            }
+            MutationOperator::FlipBits { rate } => flip_bits(code, rate, rng),
+            MutationOperator::ReplaceWord => {
+                if len == 0 {
+                    return;
+                }
+                let word = rng.gen_range(0..len);
+                code[word] = rng.gen();
+            }
+            MutationOperator::Insert => {
+                if len >= *lengths.end() {
+                    return;
+                }
+                let at = rng.gen_range(0..=len);
+                code.insert(at, rng.gen());
+            }
+            MutationOperator::Delete => {
+                if len <= *lengths.start() || len == 0 {
+                    return;
+                }
+                code.remove(rng.gen_range(0..len));
+            }
+            MutationOperator::Duplicate { max_segment } => {
+                let longest = max_segment.min(len).min(lengths.end().saturating_sub(len));
+                if longest == 0 {
+                    return;
+                }
+                let segment = rng.gen_range(1..=longest);
+                let start = rng.gen_range(0..=len-segment);
+                let copy = code[start..start+segment].to_vec();
+                code.splice(start+segment..start+segment, copy);
+            }
+            MutationOperator::Invert { max_segment } => {
+                let longest = max_segment.min(len);
+                if longest < 2 {
+                    return;
+                }
+                let segment = rng.gen_range(2..=longest);
+                let start = rng.gen_range(0..=len-segment);
+                code[start..start+segment].reverse();
+            }
+            MutationOperator::ExtByte { max_delta } => {
+                if len == 0 || max_delta == 0 {
+                    return;
+                }
+                let word = rng.gen_range(0..len);
+                // the response ext byte is the lowest byte, the event one the third
+                let shift = if rng.gen_bool(0.5) { 16 } else { 0 };
+                let delta = rng.gen_range(1..=max_delta);
+                let ext = (code[word] >> shift) as u8;
+                let ext = if rng.gen_bool(0.5) { ext.wrapping_add(delta) } else { ext.wrapping_sub(delta) };
+                code[word] = (code[word]&!(0xff << shift))|((ext as u32) << shift);
+            }
+        }
+    }
+}
+
+/// flips every bit with the chance `rate`. Instead of drawing once per bit the gaps
+/// between flipped bits are drawn from the geometric distribution, so a low rate
+/// costs a draw per flip.
+fn flip_bits(code: &mut [u32], rate: f64, rng: &mut dyn RngCore) {
+    let bits = code.len()*32;
+    if rate <= 0.0 || bits == 0 {
+        return;
+    }
+    if rate >= 1.0 {
+        code.iter_mut().for_each(|word| *word = !*word);
+        return;
+    }
+    let keep = (1.0-rate).ln();
+    let mut bit = 0usize;
+    loop {
+        let uniform: f64 = rng.gen();
+        // a gap too large for a usize saturates and ends the loop
+        bit = bit.saturating_add(((1.0-uniform).ln()/keep).floor() as usize);
+        if bit >= bits {
+            return;
+        }
+        code[bit/32] ^= 1<<(bit%32);
+        bit += 1;
+    }
+}
+
+/// an operator of a [`MutationPipeline`] and the chance it runs.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MutationStage {
+    pub chance: f64,
+    pub operator: MutationOperator,
+}
+
+/// what [`super::World::create_entity`] does to the code of a new entity. Every
+/// stage runs in order with its own chance, so a new entity can go through several
+/// mutations or none at all.
+///
+/// ```toml
+/// [mutation]
+/// min_len = 8
+/// max_len = 128
+///
+/// [[mutation.stages]]
+/// chance = 0.01
+/// operator = "insert"
+///
+/// [[mutation.stages]]
+/// chance = 1.0
+/// operator = { flip_bits = { rate = 0.0005 } }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MutationPipeline {
+    /// operators never shorten code below this length.
+    pub min_len: usize,
+    /// operators never grow code beyond this length, unbounded if `None`.
+    pub max_len: Option<usize>,
+    pub stages: Vec<MutationStage>,
+}
+/// a pipeline that never mutates.
+impl Default for MutationPipeline {
+    fn default() -> Self {
+        Self { min_len: 1, max_len: None, stages: vec![] }
+    }
+}
+impl MutationPipeline {
+    /// flips a single bit with the chance `chance`, what `mutation_chance` of
+    /// [`super::World::new`] sets up.
+    pub fn flip_bit(chance: f64) -> Self {
+        Self { stages: vec![MutationStage { chance, operator: MutationOperator::FlipBit }], ..Self::default() }
+    }
+    /// the lengths operators keep code within.
+    pub fn lengths(&self) -> RangeInclusive<usize> {
+        self.min_len..=self.max_len.unwrap_or(usize::MAX)
+    }
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_len == 0 {
+            return Err("min_len has to be at least 1".to_string());
+        }
+        if self.max_len.is_some_and(|max_len| max_len < self.min_len) {
+            return Err(format!("max_len is shorter than min_len {}", self.min_len));
+        }
+        for (idx, stage) in self.stages.iter().enumerate() {
+            if !(0.0..=1.0).contains(&stage.chance) {
+                return Err(format!("the chance of stage {idx} is not between 0 and 1"));
+            }
+            match stage.operator {
+                MutationOperator::FlipBits { rate } if !(0.0..=1.0).contains(&rate) => {
+                    return Err(format!("the rate of stage {idx} is not between 0 and 1"));
+                }
+                MutationOperator::Duplicate { max_segment: 0 } | MutationOperator::Invert { max_segment: 0 } => {
+                    return Err(format!("the segments of stage {idx} are empty"));
+                }
+                MutationOperator::ExtByte { max_delta: 0 } => {
+                    return Err(format!("stage {idx} does not change the ext byte"));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+    /// runs the stages on a copy of `code`, `None` if no stage ran. A stage with a
+    /// chance of 0 does not draw from `rng`.
+    pub fn apply(&self, code: &[u32], rng: &mut dyn RngCore) -> Option<Vec<u32>> {
+        let mut mutated = None;
+        for stage in self.stages.iter() {
+            if stage.chance != 0.0 && rng.gen_bool(stage.chance) {
+                let code = mutated.get_or_insert_with(|| code.to_vec());
+                stage.operator.mutate(code, self.lengths(), rng);
+            }
+        }
+        mutated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_pcg::Pcg64;
+    use crate::new2::entity::GPCAEntity;
+    use crate::new2::world::World;
+
+    const OPERATORS: [MutationOperator; 8] = [
+        MutationOperator::FlipBit,
+        MutationOperator::FlipBits { rate: 0.05 },
+        MutationOperator::ReplaceWord,
+        MutationOperator::Insert,
+        MutationOperator::Delete,
+        MutationOperator::Duplicate { max_segment: 5 },
+        MutationOperator::Invert { max_segment: 5 },
+        MutationOperator::ExtByte { max_delta: 3 },
+    ];
+
+    /// the rng the world mutates with.
+    fn rng(seed: u128) -> Pcg64 {
+        Pcg64::new(seed, 0xa02bdbf7bb3c0a7ac28fa16a64abf96)
+    }
+
+    #[test]
+    fn operators_keep_code_within_the_lengths() {
+        for operator in OPERATORS {
+            for (min_len, max_len) in [(1, 1), (1, 4), (3, 9), (8, 8)] {
+                for len in min_len..=max_len {
+                    let mut rng = rng(len as u128);
+                    let mut code = (0..len as u32).collect::<Vec<_>>();
+                    for _ in 0..200 {
+                        operator.mutate(&mut code, min_len..=max_len, &mut rng);
+                        assert!((min_len..=max_len).contains(&code.len()), "{operator:?} made {} words out of {min_len}..={max_len}", code.len());
+                    }
+                }
+            }
+        }
+    }
+    #[test]
+    fn operators_are_deterministic() {
+        for operator in OPERATORS {
+            let start = (0..16).map(|word| word*0x01010101).collect::<Vec<u32>>();
+            let run = || {
+                let mut rng = rng(42);
+                let mut code = start.clone();
+                for _ in 0..50 {
+                    operator.mutate(&mut code, 4..=32, &mut rng);
+                }
+                (code, rng)
+            };
+            let (code, rng) = run();
+            assert_eq!(run(), (code.clone(), rng), "{operator:?}");
+            assert_ne!(code, start, "{operator:?} never changed the code");
+        }
+    }
+    #[test]
+    fn pipelines_are_deterministic() {
+        let pipeline = MutationPipeline {
+            min_len: 4,
+            max_len: Some(32),
+            stages: OPERATORS.iter().map(|operator| MutationStage { chance: 0.5, operator: *operator }).collect(),
+        };
+        pipeline.validate().unwrap();
+        let code = (0..16).collect::<Vec<u32>>();
+        let (mut a, mut b) = (rng(7), rng(7));
+        for _ in 0..100 {
+            let mutated = pipeline.apply(&code, &mut a);
+            assert_eq!(pipeline.apply(&code, &mut b), mutated);
+            assert!(mutated.is_none_or(|code| pipeline.lengths().contains(&code.len())));
+        }
+    }
+    #[test]
+    fn mutated_entities_are_counted() {
+        let mut world = World::new(vec![], 4, 4, 4, true, 0.0, Some(1));
+        world.set_mutation(MutationPipeline::flip_bit(1.0));
+        world.create_entity(GPCAEntity::new(0, 0, 0, 0, 10, 0, vec![0; 4]));
+        world.set_mutation(MutationPipeline::default());
+        world.create_entity(GPCAEntity::new(1, 0, 0, 0, 10, 0, vec![0; 4]));
+        assert_eq!(world.tally().mutations, 1);
+        assert_eq!(world.get_entites()[1].code(), [0; 4]);
+    }
+}
@@ -0,0 +1,161 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NeighborhoodShape {
+    /// every cell within `radius` steps on both axes, a square.
+    #[default]
+    Moore,
+    /// every cell within `radius` steps counting both axes together, a diamond.
+    VonNeumann,
+}
+
+/// the cells an entity senses around itself, see [`super::World::neighbor_count`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Neighborhood {
+    pub shape: NeighborhoodShape,
+    pub radius: u32,
+    /// whether the cell of the entity itself is part of the neighborhood.
+    pub include_self: bool,
+}
+/// the eight cells directly around the entity.
+impl Default for Neighborhood {
+    fn default() -> Self {
+        Self { shape: NeighborhoodShape::Moore, radius: 1, include_self: false }
+    }
+}
+impl Neighborhood {
+    pub fn new(shape: NeighborhoodShape, radius: u32, include_self: bool) -> Self {
+        Self { shape, radius, include_self }
+    }
+    pub fn contains(&self, delta: (i64, i64)) -> bool {
+        let radius = self.radius as i64;
+        if delta == (0, 0) {
+            return self.include_self;
+        }
+        match self.shape {
+            NeighborhoodShape::Moore => delta.0.abs() <= radius && delta.1.abs() <= radius,
+            NeighborhoodShape::VonNeumann => delta.0.abs() + delta.1.abs() <= radius,
+        }
+    }
+    /// every offset in the neighborhood, row by row from the top left.
+    pub fn offsets(&self) -> impl Iterator<Item = (i64, i64)> {
+        let neighborhood = *self;
+        let radius = self.radius as i64;
+        (-radius..=radius)
+            .flat_map(move |dy| (-radius..=radius).map(move |dx| (dx, dy)))
+            .filter(move |delta| neighborhood.contains(*delta))
+    }
+}
+impl Display for Neighborhood {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let shape = match self.shape {
+            NeighborhoodShape::Moore => "moore",
+            NeighborhoodShape::VonNeumann => "von_neumann",
+        };
+        write!(f, "{shape}:{}", self.radius)?;
+        if self.include_self {
+            f.write_str(":self")?;
+        }
+        Ok(())
+    }
+}
+/// reads `shape[:radius][:self]`, e.g. `moore`, `von_neumann:2` or `moore:1:self`.
+impl FromStr for Neighborhood {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let shape = match parts.next() {
+            Some("moore") => NeighborhoodShape::Moore,
+            Some("von_neumann") => NeighborhoodShape::VonNeumann,
+            _ => return Err(format!("expected moore or von_neumann in '{s}'")),
+        };
+        let mut neighborhood = Neighborhood { shape, ..Default::default() };
+        for part in parts {
+            match part {
+                "self" => neighborhood.include_self = true,
+                radius => neighborhood.radius = radius.parse().map_err(|err| format!("invalid radius '{radius}': {err}"))?,
+            }
+        }
+        Ok(neighborhood)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new2::world::{EntityHandle, Topology, World};
+
+    /// counts by looking at every cell of the map and checking whether any of its
+    /// copies across a wrapping edge is in the neighborhood of `pos`.
+    fn brute_force(world: &World, pos: [u32; 2], neighborhood: &Neighborhood) -> usize {
+        let (wrap_x, wrap_y) = world.topology().wraps();
+        let radius = neighborhood.radius as i64;
+        let copies = |delta: i64, len: u32, wraps: bool| -> Vec<i64> {
+            if !wraps {
+                return vec![delta];
+            }
+            let len = len as i64;
+            (-radius/len-1..=radius/len+1).map(|k| delta+k*len).collect()
+        };
+        let mut count = 0;
+        for y in 0..world.height() {
+            for x in 0..world.width() {
+                let reached = if [x, y] == pos {
+                    neighborhood.include_self
+                } else {
+                    let dxs = copies(x as i64-pos[0] as i64, world.width(), wrap_x);
+                    let dys = copies(y as i64-pos[1] as i64, world.height(), wrap_y);
+                    dxs.iter().any(|dx| dys.iter().any(|dy| (*dx, *dy) != (0, 0) && neighborhood.contains((*dx, *dy))))
+                };
+                if reached && world.get(x, y) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn counts_match_a_brute_force_reference() {
+        let occupant = Some(EntityHandle { index: 0, generation: 0 });
+        for width in 1..=5 {
+            for height in 1..=4 {
+                for pattern in 0..3u32 {
+                    let mut world = World::new(vec![], 0, width, height, true, 0.0, Some(1));
+                    for (idx, cell) in world.map.iter_mut().enumerate() {
+                        let (x, y) = (idx as u32%width, idx as u32/width);
+                        let occupied = match pattern {
+                            0 => true,
+                            1 => (x+2*y)%3 != 0,
+                            _ => (x+y)%2 == 0,
+                        };
+                        *cell = if occupied { occupant } else { None };
+                    }
+                    for topology in [Topology::Bounded, Topology::Toroidal, Topology::Cylinder] {
+                        world.set_topology(topology);
+                        for shape in [NeighborhoodShape::Moore, NeighborhoodShape::VonNeumann] {
+                            for radius in 0..=3 {
+                                for include_self in [false, true] {
+                                    let neighborhood = Neighborhood::new(shape, radius, include_self);
+                                    for y in 0..height {
+                                        for x in 0..width {
+                                            assert_eq!(
+                                                world.count_neighbors([x, y], &neighborhood),
+                                                brute_force(&world, [x, y], &neighborhood),
+                                                "{neighborhood} at {x},{y} on a {topology} {width}x{height} map, pattern {pattern}",
+                                            );
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
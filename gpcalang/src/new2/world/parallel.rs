@@ -0,0 +1,163 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Usage, World};
+use super::Executed;
+use crate::new2::entity::{Effect, GPCAEntity, GPCAEntityInternal};
+
+/// how [`World::step`] runs the entities of a step.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepMode {
+    /// one entity after another, every entity sees what the ones before it did.
+    #[default]
+    Sequential,
+    /// every entity decides against the world as it was at the start of the step,
+    /// spread over [`World::threads`] threads. The decisions are then applied one
+    /// after another in update order, so the first entity to move into a cell gets it
+    /// and the ones after it stay where they are. The result does not depend on the
+    /// number of threads.
+    Parallel,
+}
+impl Display for StepMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            StepMode::Sequential => "sequential",
+            StepMode::Parallel => "parallel",
+        })
+    }
+}
+impl FromStr for StepMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sequential" => Ok(StepMode::Sequential),
+            "parallel" => Ok(StepMode::Parallel),
+            _ => Err(format!("expected sequential or parallel, found '{s}'")),
+        }
+    }
+}
+
+/// what an entity decided to do in a parallel step.
+enum Proposal {
+    /// the entity starved or was already taken off the map.
+    Die,
+    /// the state of the entity after its turn, what is left for the world, the
+    /// instructions it ran if the world traces and what kind they were.
+    Step(GPCAEntityInternal, Effect, Vec<Executed>, Usage),
+}
+
+impl World {
+    /// runs the turn of `entity` on a copy of its state without changing the world.
+    fn propose(&self, entity: &GPCAEntity) -> Proposal {
+        if !self.is_alive(entity.handle()) {
+            return Proposal::Die;
+        }
+        let mut internal = *entity.inner();
+        if self.use_energy {
+            if internal.get_energy() == 0 {
+                return Proposal::Die;
+            }
+            internal.spend_energy(self.costs.tick);
+        }
+        let mut executed = vec![];
+        let mut usage = Usage::default();
+        let effect = if self.is_tracing() {
+            self.turn(&mut internal, entity.program(), |instruction| {
+                usage.count(&instruction);
+                executed.push(instruction);
+            })
+        } else {
+            self.turn(&mut internal, entity.program(), |instruction| usage.count(&instruction))
+        };
+        Proposal::Step(internal, effect, executed, usage)
+    }
+    fn proposals(&self) -> Vec<Proposal> {
+        let threads = self.threads.min(self.entities.len());
+        if threads <= 1 {
+            return self.entities.iter().map(|entity| self.propose(entity)).collect();
+        }
+        let chunk = self.entities.len().div_ceil(threads);
+        std::thread::scope(|scope| {
+            let workers = self.entities.chunks(chunk)
+                .map(|entities| scope.spawn(move || entities.iter().map(|entity| self.propose(entity)).collect::<Vec<_>>()))
+                .collect::<Vec<_>>();
+            workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+        })
+    }
+    /// decides for every entity in parallel, then applies the decisions in update
+    /// order. Entities spawned during the step act from the next step on, entities
+    /// taken off the map are dropped at the end of it.
+    pub(crate) fn step_parallel<F, H>(&mut self, clear: &mut F, place: &mut H)
+        where F: FnMut(&GPCAEntity),
+            H: FnMut(&GPCAEntity) {
+        let proposals = self.proposals();
+        // every entity is updated before any effect runs so user functions that change
+        // another entity are not overwritten by its own update
+        for (idx, proposal) in proposals.iter().enumerate() {
+            match proposal {
+                Proposal::Die => {
+                    // only an entity that starved is still on the map
+                    if self.is_alive(self.entities[idx].handle()) {
+                        self.remove(self.entities[idx].x(), self.entities[idx].y());
+                        self.tally.starved += 1;
+                    }
+                }
+                Proposal::Step(internal, _, _, usage) => {
+                    *self.entities[idx].inner_mut() = *internal;
+                    self.tally.usage += *usage;
+                }
+            }
+        }
+        for (idx, proposal) in proposals.into_iter().enumerate() {
+            if let Proposal::Step(_, effect, executed, _) = proposal {
+                if self.is_alive(self.entities[idx].handle()) {
+                    self.perform(idx, effect, clear, place);
+                }
+                self.trace(idx, executed);
+            }
+        }
+        // decided before anything is dropped since dropping shifts the entities behind it
+        let alive = self.entities.iter().map(|entity| self.is_alive(entity.handle())).collect::<Vec<_>>();
+        for (entity, _) in self.entities.iter().zip(&alive).filter(|(_, alive)| !**alive) {
+            clear(entity);
+            self.slots.remove(entity.handle());
+        }
+        let mut alive = alive.into_iter();
+        self.entities.retain(|_| alive.next().unwrap());
+        for (idx, entity) in self.entities.iter().enumerate() {
+            self.slots.set(entity.handle(), idx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new2::world::{actions, WorldAction};
+
+    /// the snapshot after `steps` parallel steps on `threads` threads.
+    fn run(threads: usize, steps: usize) -> Vec<u8> {
+        let functions: Vec<Box<dyn WorldAction>> = vec![Box::new(actions::EAT_TOP), Box::new(actions::BREED_TOP_LEFT), Box::new(actions::REPRODUCE_TOP)];
+        let mut world = World::new(functions, 512, 48, 48, true, 0.05, Some(3));
+        world.set_step_mode(StepMode::Parallel);
+        world.set_threads(threads);
+        world.populate(512, 300, 24);
+        for _ in 0..steps {
+            world.step(|_| {}, |_| {});
+        }
+        assert!(!world.get_entites().is_empty());
+        let mut bytes = vec![];
+        world.save(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn thread_count_does_not_change_the_result() {
+        let single = run(1, 40);
+        for threads in [2, 3, 8] {
+            assert!(run(threads, 40) == single, "{threads} threads diverged from one");
+        }
+    }
+}
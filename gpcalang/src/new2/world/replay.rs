@@ -0,0 +1,295 @@
+use std::{fmt::Display, io::{Read, Write}};
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use super::{Layer, World};
+use crate::new2::entity::GPCAEntity;
+
+const MAGIC: [u8; 4] = *b"GPCR";
+pub const REPLAY_VERSION: u32 = 4;
+
+/// 64 bit FNV-1a. Unlike the std hashers its output is fixed, so logs written by one
+/// build can be checked by another.
+struct Fnv(u64);
+impl Fnv {
+    fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+}
+fn hash_entity(entity: &GPCAEntity) -> u64 {
+    let snapshot = entity.snapshot();
+    let mut hasher = Fnv::new();
+    hasher.write_u64(snapshot.registers[0]);
+    hasher.write_u64(snapshot.registers[1]);
+    hasher.write_u32(snapshot.pos[0]);
+    hasher.write_u32(snapshot.pos[1]);
+    hasher.write_u32(snapshot.handle.index);
+    hasher.write_u32(snapshot.handle.generation);
+    hasher.write_u32(snapshot.energy);
+    hasher.write_u64(snapshot.rip);
+    hasher.write_u32(snapshot.color);
+    for word in snapshot.code.iter() {
+        hasher.write_u32(*word);
+    }
+    hasher.0
+}
+
+/// hashes of the world state after a step.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepRecord {
+    pub map: u64,
+    /// one hash per world layer.
+    pub layers: Vec<u64>,
+    pub signal: u64,
+    /// fingerprint of the rng, the next two outputs of a copy of it.
+    pub rng: u64,
+    /// one hash per entity in update order.
+    pub entities: Vec<u64>,
+}
+impl StepRecord {
+    pub fn capture(world: &World) -> Self {
+        let mut map = Fnv::new();
+        for cell in world.map.iter() {
+            match cell {
+                Some(handle) => {
+                    map.write_u32(handle.index);
+                    map.write_u32(handle.generation);
+                }
+                None => map.write_u32(u32::MAX),
+            }
+        }
+        let hash_layer = |layer: &Layer| {
+            let mut hasher = Fnv::new();
+            for cell in layer.cells() {
+                hasher.write_u32(*cell);
+            }
+            hasher.0
+        };
+        let layers = world.layers.iter().map(hash_layer).collect();
+        let signal = hash_layer(&world.signal);
+        let mut pseudo = world.pseudo.clone();
+        let mut rng = Fnv::new();
+        rng.write_u64(pseudo.next_u64());
+        rng.write_u64(pseudo.next_u64());
+        Self { map: map.0, layers, signal, rng: rng.0, entities: world.get_entites().iter().map(hash_entity).collect() }
+    }
+}
+
+/// per step hashes of a run. The first record is the state the recording started
+/// from, record `n` is the state after `n` steps.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayLog {
+    pub records: Vec<StepRecord>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DivergenceKind {
+    /// the entity at this index in the update order differs.
+    Entity(usize),
+    /// every shared entity matches but the populations have different sizes.
+    EntityCount { expected: usize, found: usize },
+    Map,
+    /// the world layer at this index differs.
+    Layer(usize),
+    Signal,
+    Rng,
+}
+/// the first point at which a replay differs from its log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    /// number of steps taken when the difference was found, 0 is the starting state.
+    pub step: usize,
+    pub kind: DivergenceKind,
+}
+impl Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "diverged at step {}: ", self.step)?;
+        match self.kind {
+            DivergenceKind::Entity(idx) => write!(f, "entity {idx} differs"),
+            DivergenceKind::EntityCount { expected, found } => write!(f, "expected {expected} entities, found {found}"),
+            DivergenceKind::Map => write!(f, "the map differs"),
+            DivergenceKind::Layer(idx) => write!(f, "layer {idx} differs"),
+            DivergenceKind::Signal => write!(f, "the signal differs"),
+            DivergenceKind::Rng => write!(f, "the rng state differs"),
+        }
+    }
+}
+impl StepRecord {
+    /// entities are checked first since a differing entity usually also changes the
+    /// map and that would hide which entity caused it.
+    fn compare(&self, found: &StepRecord, step: usize) -> Result<(), Divergence> {
+        let diverged = |kind| Err(Divergence { step, kind });
+        if let Some(idx) = self.entities.iter().zip(found.entities.iter()).position(|(expected, found)| expected != found) {
+            return diverged(DivergenceKind::Entity(idx));
+        }
+        if self.entities.len() != found.entities.len() {
+            return diverged(DivergenceKind::EntityCount { expected: self.entities.len(), found: found.entities.len() });
+        }
+        if self.map != found.map {
+            return diverged(DivergenceKind::Map);
+        }
+        if let Some(idx) = self.layers.iter().zip(found.layers.iter()).position(|(expected, found)| expected != found) {
+            return diverged(DivergenceKind::Layer(idx));
+        }
+        if self.signal != found.signal {
+            return diverged(DivergenceKind::Signal);
+        }
+        if self.rng != found.rng {
+            return diverged(DivergenceKind::Rng);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    NotALog,
+    Version(u32),
+    Encoding(bincode::Error),
+}
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::Io(err) => write!(f, "{err}"),
+            ReplayError::NotALog => write!(f, "not a replay log"),
+            ReplayError::Version(version) => write!(f, "replay log version {version} is not supported, expected {REPLAY_VERSION}"),
+            ReplayError::Encoding(err) => write!(f, "invalid replay log: {err}"),
+        }
+    }
+}
+impl std::error::Error for ReplayError {}
+impl From<std::io::Error> for ReplayError {
+    fn from(value: std::io::Error) -> Self {
+        ReplayError::Io(value)
+    }
+}
+
+impl ReplayLog {
+    pub fn save(&self, mut writer: impl Write) -> Result<(), ReplayError> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&REPLAY_VERSION.to_le_bytes())?;
+        bincode::serialize_into(&mut writer, self).map_err(ReplayError::Encoding)?;
+        writer.flush()?;
+        Ok(())
+    }
+    pub fn load(mut reader: impl Read) -> Result<Self, ReplayError> {
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        if header[..4] != MAGIC {
+            return Err(ReplayError::NotALog);
+        }
+        let version = u32::from_le_bytes(header[4..].try_into().unwrap());
+        if version != REPLAY_VERSION {
+            return Err(ReplayError::Version(version));
+        }
+        bincode::deserialize_from(reader).map_err(ReplayError::Encoding)
+    }
+    /// number of steps covered by the log.
+    pub fn steps(&self) -> usize {
+        self.records.len().saturating_sub(1)
+    }
+    /// steps `world` as many times as the log recorded and returns the first
+    /// difference. `world` has to be in the state the recording started from, built
+    /// from the same seed or loaded from the same snapshot.
+    pub fn replay(&self, world: &mut World) -> Result<(), Divergence> {
+        for (step, expected) in self.records.iter().enumerate() {
+            if step != 0 {
+                world.step(|_| {}, |_| {});
+            }
+            expected.compare(&StepRecord::capture(world), step)?;
+        }
+        Ok(())
+    }
+}
+
+impl World {
+    /// records the current state and then the state after every [`World::step`] until
+    /// [`World::stop_recording`]. Every record keeps one hash per entity, so long runs
+    /// with large populations grow the log accordingly.
+    pub fn start_recording(&mut self) {
+        let record = StepRecord::capture(self);
+        self.recorder = Some(ReplayLog { records: vec![record] });
+    }
+    pub fn stop_recording(&mut self) -> Option<ReplayLog> {
+        self.recorder.take()
+    }
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+    pub(crate) fn record_step(&mut self) {
+        if !self.is_recording() {
+            return;
+        }
+        let record = StepRecord::capture(self);
+        if let Some(log) = self.recorder.as_mut() {
+            log.records.push(record);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new2::world::{actions, WorldAction};
+
+    fn functions() -> Vec<Box<dyn WorldAction>> {
+        vec![Box::new(actions::EAT_TOP), Box::new(actions::BREED_TOP_LEFT), Box::new(actions::REPRODUCE_TOP)]
+    }
+    /// a populated world and its snapshot, so replays can start from the same state.
+    fn start() -> (World, Vec<u8>) {
+        let mut world = World::new(functions(), 512, 32, 32, true, 0.05, Some(11));
+        world.populate(512, 200, 24);
+        let mut snapshot = vec![];
+        world.save(&mut snapshot).unwrap();
+        (world, snapshot)
+    }
+    fn record(world: &mut World, steps: usize) -> ReplayLog {
+        world.start_recording();
+        for _ in 0..steps {
+            world.step(|_| {}, |_| {});
+        }
+        world.stop_recording().unwrap()
+    }
+
+    #[test]
+    fn replays_match_their_recording() {
+        let (mut world, snapshot) = start();
+        let log = record(&mut world, 30);
+        assert_eq!(log.steps(), 30);
+        let mut replayed = World::load(&snapshot[..], functions()).unwrap();
+        assert_eq!(log.replay(&mut replayed), Ok(()));
+    }
+    #[test]
+    fn divergences_report_their_step_and_entity() {
+        let (mut world, snapshot) = start();
+        let mut log = record(&mut world, 30);
+        let entity = log.records[12].entities.len()/2;
+        log.records[12].entities[entity] ^= 1;
+        let mut replayed = World::load(&snapshot[..], functions()).unwrap();
+        assert_eq!(log.replay(&mut replayed), Err(Divergence { step: 12, kind: DivergenceKind::Entity(entity) }));
+    }
+    #[test]
+    fn changed_entities_are_found() {
+        let (mut world, snapshot) = start();
+        let log = record(&mut world, 10);
+        let mut replayed = World::load(&snapshot[..], functions()).unwrap();
+        let handle = replayed.get_entites()[3].handle();
+        let entity = replayed.entity_mut(handle).unwrap();
+        entity.set_energy(entity.get_energy()+1);
+        assert_eq!(log.replay(&mut replayed), Err(Divergence { step: 0, kind: DivergenceKind::Entity(3) }));
+    }
+}
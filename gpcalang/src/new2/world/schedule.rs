@@ -0,0 +1,141 @@
+use std::{fmt::Display, str::FromStr};
+
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use super::World;
+
+/// the order [`World::step`] updates entities in. Entities earlier in the order win
+/// contested cells, the schedule decides who that is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Schedule {
+    /// the order entities were added in, dead entities are replaced by the last one.
+    #[default]
+    Sequential,
+    /// a new permutation every step, drawn from the world rng.
+    Random,
+    /// the order of the previous step shifted by one, so every entity takes its turn
+    /// at going first.
+    RoundRobin,
+    /// the entities with the most energy first, ties keep their previous order.
+    Energy,
+}
+impl Display for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Schedule::Sequential => "sequential",
+            Schedule::Random => "random",
+            Schedule::RoundRobin => "round_robin",
+            Schedule::Energy => "energy",
+        })
+    }
+}
+impl FromStr for Schedule {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sequential" => Ok(Schedule::Sequential),
+            "random" => Ok(Schedule::Random),
+            "round_robin" => Ok(Schedule::RoundRobin),
+            "energy" => Ok(Schedule::Energy),
+            _ => Err(format!("expected sequential, random, round_robin or energy, found '{s}'")),
+        }
+    }
+}
+
+impl World {
+    /// puts the entities in the order the schedule updates them in this step.
+    pub(crate) fn schedule_entities(&mut self) {
+        match self.schedule {
+            Schedule::Sequential => return,
+            Schedule::Random => self.entities.shuffle(&mut self.pseudo),
+            Schedule::RoundRobin => {
+                if !self.entities.is_empty() {
+                    self.entities.rotate_left(1);
+                }
+            }
+            Schedule::Energy => self.entities.sort_by_key(|entity| std::cmp::Reverse(entity.get_energy())),
+        }
+        for (idx, entity) in self.entities.iter().enumerate() {
+            self.slots.set(entity.handle(), idx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new2::entity::GPCAEntity;
+    use crate::new2::world::{actions, EntityHandle, WorldAction};
+
+    /// a world with an entity of each energy in a row, and their handles.
+    fn world(schedule: Schedule, energies: &[u32]) -> (World, Vec<EntityHandle>) {
+        let mut world = World::new(vec![], energies.len(), energies.len() as u32, 1, true, 0.0, Some(9));
+        world.set_schedule(schedule);
+        let handles = energies.iter().enumerate().map(|(x, energy)| world.push_entity(GPCAEntity::new(x as u32, 0, 0, 0, *energy, 0, vec![0]))).collect();
+        (world, handles)
+    }
+    /// the update order after scheduling, checking that every handle still resolves to
+    /// its position.
+    fn schedule(world: &mut World) -> Vec<EntityHandle> {
+        world.schedule_entities();
+        let order = world.get_entites().iter().map(|entity| entity.handle()).collect::<Vec<_>>();
+        for (idx, handle) in order.iter().enumerate() {
+            assert_eq!(world.slots.get(*handle), Some(idx));
+        }
+        order
+    }
+
+    #[test]
+    fn random_orders_follow_the_seed() {
+        let (mut a, handles) = world(Schedule::Random, &[1; 12]);
+        let (mut b, _) = world(Schedule::Random, &[1; 12]);
+        let orders = (0..4).map(|_| schedule(&mut a)).collect::<Vec<_>>();
+        assert_eq!((0..4).map(|_| schedule(&mut b)).collect::<Vec<_>>(), orders);
+        assert!(orders.iter().any(|order| *order != handles));
+    }
+    #[test]
+    fn random_orders_survive_snapshots() {
+        let functions = || -> Vec<Box<dyn WorldAction>> { vec![Box::new(actions::EAT_TOP), Box::new(actions::REPRODUCE_TOP)] };
+        let save = |world: &World| {
+            let mut bytes = vec![];
+            world.save(&mut bytes).unwrap();
+            bytes
+        };
+        let mut world = World::new(functions(), 256, 32, 32, true, 0.0, Some(4));
+        world.set_schedule(Schedule::Random);
+        world.populate(256, 200, 16);
+        for _ in 0..5 {
+            world.step(|_| {}, |_| {});
+        }
+        let mut loaded = World::load(&save(&world)[..], functions()).unwrap();
+        assert_eq!(loaded.schedule(), Schedule::Random);
+        for _ in 0..10 {
+            world.step(|_| {}, |_| {});
+            loaded.step(|_| {}, |_| {});
+            assert!(save(&world) == save(&loaded), "step {}", world.steps());
+        }
+    }
+    #[test]
+    fn round_robin_rotates_by_one() {
+        let (mut world, handles) = world(Schedule::RoundRobin, &[1; 5]);
+        for step in 1..=6 {
+            let mut expected = handles.clone();
+            expected.rotate_left(step%handles.len());
+            assert_eq!(schedule(&mut world), expected, "step {step}");
+        }
+    }
+    #[test]
+    fn energy_orders_by_energy_keeping_ties() {
+        let (mut world, h) = world(Schedule::Energy, &[5, 9, 5, 1, 9]);
+        assert_eq!(schedule(&mut world), [h[1], h[4], h[0], h[2], h[3]]);
+        world.entity_mut(h[3]).unwrap().set_energy(9);
+        assert_eq!(schedule(&mut world), [h[1], h[4], h[3], h[0], h[2]]);
+    }
+    #[test]
+    fn sequential_keeps_the_order() {
+        let (mut world, handles) = world(Schedule::Sequential, &[3, 1, 2]);
+        assert_eq!(schedule(&mut world), handles);
+    }
+}
@@ -0,0 +1,204 @@
+use std::{fmt::Display, io::{Read, Write}};
+
+use serde::{Deserialize, Serialize};
+
+use super::{CostModel, CrossoverOperator, EntityHandle, Layer, MutationPipeline, Neighborhood, Schedule, Slots, StepMode, Tally, Topology, World, WorldAction};
+use crate::new2::entity::{EntitySnapshot, GPCAEntity};
+
+/// first bytes of every snapshot.
+const MAGIC: [u8; 4] = *b"GPCA";
+/// bumped whenever the layout of [`WorldSnapshot`] changes.
+pub const SNAPSHOT_VERSION: u32 = 12;
+
+#[derive(Serialize, Deserialize)]
+struct WorldSnapshot {
+    width: u32,
+    height: u32,
+    use_energy: bool,
+    mutation: MutationPipeline,
+    crossover: CrossoverOperator,
+    topology: Topology,
+    neighborhoods: Vec<Neighborhood>,
+    map: Vec<Option<EntityHandle>>,
+    layers: Vec<Layer>,
+    signal: Layer,
+    schedule: Schedule,
+    costs: CostModel,
+    step_mode: StepMode,
+    entities: Vec<EntitySnapshot>,
+    slots: Slots,
+    pseudo: rand_pcg::Pcg64,
+    steps: u64,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(std::io::Error),
+    /// the data does not start with the snapshot magic.
+    NotASnapshot,
+    /// the snapshot was written by a different version of the format.
+    Version(u32),
+    Encoding(bincode::Error),
+    /// the snapshot decoded but its contents do not describe a valid world.
+    Corrupt(String),
+}
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Io(err) => write!(f, "{err}"),
+            SnapshotError::NotASnapshot => write!(f, "not a world snapshot"),
+            SnapshotError::Version(version) => write!(f, "snapshot version {version} is not supported, expected {SNAPSHOT_VERSION}"),
+            SnapshotError::Encoding(err) => write!(f, "invalid snapshot: {err}"),
+            SnapshotError::Corrupt(reason) => write!(f, "corrupt snapshot: {reason}"),
+        }
+    }
+}
+impl std::error::Error for SnapshotError {}
+impl From<std::io::Error> for SnapshotError {
+    fn from(value: std::io::Error) -> Self {
+        SnapshotError::Io(value)
+    }
+}
+
+impl World {
+    /// writes the map, every entity, the rng and the world flags. User functions are
+    /// not part of the snapshot and have to be given again to [`World::load`].
+    pub fn save(&self, mut writer: impl Write) -> Result<(), SnapshotError> {
+        let snapshot = WorldSnapshot {
+            width: self.width,
+            height: self.height,
+            use_energy: self.use_energy,
+            mutation: self.mutation.clone(),
+            crossover: self.crossover,
+            topology: self.topology,
+            neighborhoods: self.neighborhoods.clone(),
+            map: self.map.clone(),
+            layers: self.layers.clone(),
+            signal: self.signal.clone(),
+            schedule: self.schedule,
+            costs: self.costs,
+            step_mode: self.step_mode,
+            entities: self.get_entites().iter().map(|entity| entity.snapshot()).collect(),
+            slots: self.slots.clone(),
+            pseudo: self.pseudo.clone(),
+            steps: self.steps,
+        };
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+        bincode::serialize_into(&mut writer, &snapshot).map_err(SnapshotError::Encoding)?;
+        writer.flush()?;
+        Ok(())
+    }
+    /// restores a world written by [`World::save`], stepping it continues exactly where
+    /// the saved world left off. The number of threads is not saved, the loaded world
+    /// uses the available parallelism.
+    pub fn load(mut reader: impl Read, functions: Vec<Box<dyn WorldAction>>) -> Result<World, SnapshotError> {
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        if header[..4] != MAGIC {
+            return Err(SnapshotError::NotASnapshot);
+        }
+        let version = u32::from_le_bytes(header[4..].try_into().unwrap());
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::Version(version));
+        }
+        let snapshot: WorldSnapshot = bincode::deserialize_from(reader).map_err(SnapshotError::Encoding)?;
+        let cells = snapshot.width.checked_mul(snapshot.height).ok_or_else(|| SnapshotError::Corrupt(format!("{}x{} cells do not fit in a u32", snapshot.width, snapshot.height)))?;
+        if snapshot.map.len() != cells as usize {
+            return Err(SnapshotError::Corrupt(format!("map has {} cells but the world is {}x{}", snapshot.map.len(), snapshot.width, snapshot.height)));
+        }
+        if let Some(layer) = snapshot.layers.iter().chain([&snapshot.signal]).find(|layer| layer.cells.len() != cells as usize) {
+            return Err(SnapshotError::Corrupt(format!("layer '{}' has {} cells but the world is {}x{}", layer.name(), layer.cells.len(), snapshot.width, snapshot.height)));
+        }
+        if snapshot.neighborhoods.is_empty() {
+            return Err(SnapshotError::Corrupt("the world has no neighborhood".to_string()));
+        }
+        if let Err(err) = snapshot.mutation.validate() {
+            return Err(SnapshotError::Corrupt(format!("invalid mutation pipeline: {err}")));
+        }
+        if snapshot.costs.budget == 0 {
+            return Err(SnapshotError::Corrupt("entities have no instruction budget".to_string()));
+        }
+        if let Some(entity) = snapshot.entities.iter().find(|entity| entity.pos[0] >= snapshot.width || entity.pos[1] >= snapshot.height) {
+            return Err(SnapshotError::Corrupt(format!("entity {} is outside of the map at {:?}", entity.handle, entity.pos)));
+        }
+        if let Some((_, entity)) = snapshot.entities.iter().enumerate().find(|(idx, entity)| snapshot.slots.get(entity.handle) != Some(*idx)) {
+            return Err(SnapshotError::Corrupt(format!("handle {} does not refer to its entity", entity.handle)));
+        }
+        if !snapshot.slots.is_consistent(snapshot.entities.len()) {
+            return Err(SnapshotError::Corrupt("the entity slots do not match the entities".to_string()));
+        }
+        let width = snapshot.width;
+        let misplaced = snapshot.map.iter().enumerate().find(|(cell, handle)| handle.is_some_and(|handle| {
+            let pos = snapshot.slots.get(handle).map(|idx| snapshot.entities[idx].pos);
+            pos != Some([*cell as u32%width, *cell as u32/width])
+        }));
+        if let Some((cell, _)) = misplaced {
+            return Err(SnapshotError::Corrupt(format!("cell {cell} of the map refers to an entity that is not there")));
+        }
+        let entities = snapshot.entities.into_iter().map(GPCAEntity::from_snapshot).collect::<Vec<_>>();
+        Ok(World {
+            functions,
+            entities,
+            slots: snapshot.slots,
+            map: snapshot.map,
+            pseudo: snapshot.pseudo,
+            width: snapshot.width,
+            height: snapshot.height,
+            use_energy: snapshot.use_energy,
+            mutation: snapshot.mutation,
+            crossover: snapshot.crossover,
+            topology: snapshot.topology,
+            neighborhoods: snapshot.neighborhoods,
+            layers: snapshot.layers,
+            signal: snapshot.signal,
+            schedule: snapshot.schedule,
+            costs: snapshot.costs,
+            step_mode: snapshot.step_mode,
+            threads: World::default_threads(),
+            steps: snapshot.steps,
+            tally: Tally::default(),
+            recorder: None,
+            tracer: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new2::world::actions;
+
+    fn functions() -> Vec<Box<dyn WorldAction>> {
+        vec![Box::new(actions::EAT_TOP), Box::new(actions::BREED_TOP_LEFT), Box::new(actions::REPRODUCE_TOP)]
+    }
+    fn save(world: &World) -> Vec<u8> {
+        let mut bytes = vec![];
+        world.save(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn loaded_worlds_continue_bit_for_bit() {
+        let mut world = World::new(functions(), 512, 48, 48, true, 0.05, Some(7));
+        world.populate(512, 300, 24);
+        for _ in 0..20 {
+            world.step(|_| {}, |_| {});
+        }
+        let mut loaded = World::load(&save(&world)[..], functions()).unwrap();
+        assert_eq!(save(&loaded), save(&world));
+        for _ in 0..40 {
+            world.step(|_| {}, |_| {});
+            loaded.step(|_| {}, |_| {});
+            assert_eq!(save(&loaded), save(&world), "step {}", world.steps());
+        }
+        assert!(!world.get_entites().is_empty());
+    }
+    #[test]
+    fn snapshots_are_checked() {
+        assert!(matches!(World::load(&b"GPCB\0\0\0\0"[..], vec![]), Err(SnapshotError::NotASnapshot)));
+        let mut bytes = save(&World::new(vec![], 0, 4, 4, false, 0.0, None));
+        bytes[4..8].copy_from_slice(&(SNAPSHOT_VERSION+1).to_le_bytes());
+        assert!(matches!(World::load(&bytes[..], vec![]), Err(SnapshotError::Version(version)) if version == SNAPSHOT_VERSION+1));
+    }
+}
@@ -0,0 +1,131 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// how the edges of the map connect.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Topology {
+    /// the edges are walls, nothing moves or looks past them.
+    #[default]
+    Bounded,
+    /// both axes wrap around.
+    Toroidal,
+    /// only the x axis wraps around, the top and bottom edges are walls.
+    Cylinder,
+}
+impl Topology {
+    /// whether the x and y axis wrap around.
+    pub fn wraps(&self) -> (bool, bool) {
+        match self {
+            Topology::Bounded => (false, false),
+            Topology::Toroidal => (true, true),
+            Topology::Cylinder => (true, false),
+        }
+    }
+    fn axis(wraps: bool, coord: u32, delta: i64, len: u32) -> Option<u32> {
+        let moved = coord as i64 + delta;
+        if wraps {
+            Some(moved.rem_euclid(len as i64) as u32)
+        } else if moved < 0 || moved >= len as i64 {
+            None
+        } else {
+            Some(moved as u32)
+        }
+    }
+    /// the cell `delta` away from `pos` on a `width` by `height` map, or `None` if that
+    /// crosses an edge that does not wrap.
+    pub fn offset(&self, pos: [u32; 2], delta: (i64, i64), width: u32, height: u32) -> Option<[u32; 2]> {
+        let (wrap_x, wrap_y) = self.wraps();
+        Some([
+            Self::axis(wrap_x, pos[0], delta.0, width)?,
+            Self::axis(wrap_y, pos[1], delta.1, height)?,
+        ])
+    }
+}
+impl Display for Topology {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Topology::Bounded => "bounded",
+            Topology::Toroidal => "toroidal",
+            Topology::Cylinder => "cylinder",
+        })
+    }
+}
+impl FromStr for Topology {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bounded" => Ok(Topology::Bounded),
+            "toroidal" => Ok(Topology::Toroidal),
+            "cylinder" => Ok(Topology::Cylinder),
+            _ => Err(format!("expected bounded, toroidal or cylinder, got '{s}'")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new2::entity::{Direction, GPCAEntity};
+    use crate::new2::world::{Neighborhood, World};
+
+    const TOPOLOGIES: [Topology; 3] = [Topology::Bounded, Topology::Toroidal, Topology::Cylinder];
+
+    fn step(topology: Topology, pos: [u32; 2], direction: Direction) -> Option<[u32; 2]> {
+        topology.offset(pos, direction.delta(), 4, 3)
+    }
+
+    #[test]
+    fn toroidal_maps_wrap_at_every_edge() {
+        let topology = Topology::Toroidal;
+        assert_eq!(step(topology, [0, 1], Direction::Left), Some([3, 1]));
+        assert_eq!(step(topology, [3, 1], Direction::Right), Some([0, 1]));
+        assert_eq!(step(topology, [1, 0], Direction::Bottom), Some([1, 2]));
+        assert_eq!(step(topology, [1, 2], Direction::Top), Some([1, 0]));
+        assert_eq!(step(topology, [0, 0], Direction::BottomLeft), Some([3, 2]));
+        assert_eq!(step(topology, [3, 2], Direction::TopRight), Some([0, 0]));
+        assert_eq!(topology.offset([0, 0], (-9, 7), 4, 3), Some([3, 1]));
+    }
+    #[test]
+    fn cylinders_only_wrap_x() {
+        let topology = Topology::Cylinder;
+        assert_eq!(step(topology, [0, 1], Direction::Left), Some([3, 1]));
+        assert_eq!(step(topology, [3, 1], Direction::Right), Some([0, 1]));
+        assert_eq!(step(topology, [3, 1], Direction::TopRight), Some([0, 2]));
+        assert_eq!(step(topology, [1, 0], Direction::Bottom), None);
+        assert_eq!(step(topology, [1, 2], Direction::Top), None);
+        assert_eq!(step(topology, [0, 0], Direction::BottomLeft), None);
+    }
+    #[test]
+    fn bounded_maps_stop_at_every_edge() {
+        let topology = Topology::Bounded;
+        assert_eq!(step(topology, [0, 1], Direction::Left), None);
+        assert_eq!(step(topology, [3, 1], Direction::Right), None);
+        assert_eq!(step(topology, [1, 0], Direction::Bottom), None);
+        assert_eq!(step(topology, [1, 2], Direction::Top), None);
+        assert_eq!(step(topology, [3, 2], Direction::TopRight), None);
+        assert_eq!(step(topology, [1, 1], Direction::TopLeft), Some([0, 2]));
+        assert_eq!(topology.wraps(), (false, false));
+    }
+    #[test]
+    fn movement_and_neighbor_counts_agree() {
+        for topology in TOPOLOGIES {
+            for y in 0..3 {
+                for x in 0..4 {
+                    for direction in Direction::ALL {
+                        let mut world = World::new(vec![], 1, 4, 3, true, 0.0, Some(1));
+                        world.set_topology(topology);
+                        world.push_entity(GPCAEntity::new(x, y, 0, 0, 10, 0, vec![0]));
+                        world.move_entity(0, direction);
+                        let moved = world.get_entites()[0].pos();
+                        let context = format!("{direction:?} from {x},{y} on a {topology} map");
+                        assert_eq!(Some(moved).filter(|moved| *moved != [x, y]), world.position_at_direction([x, y], direction), "{context}");
+                        assert!(world.entity_at(moved).is_some(), "{context}");
+                        assert_eq!(world.count_neighbors([x, y], &Neighborhood::default()), usize::from(moved != [x, y]), "{context}");
+                    }
+                }
+            }
+        }
+    }
+}
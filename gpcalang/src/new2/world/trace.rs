@@ -0,0 +1,372 @@
+use std::{collections::{BTreeMap, VecDeque}, io::{Read, Write}, sync::{Arc, Mutex}};
+
+use serde::Serialize;
+
+use super::{EntityHandle, Executed, World};
+use crate::new2::entity::bytecode::Register;
+
+/// one instruction an entity ran during [`World::step`].
+#[derive(Clone, Copy, Debug)]
+pub struct TraceRecord {
+    /// number of steps the world had taken before the one the instruction ran in.
+    pub step: u64,
+    pub entity: EntityHandle,
+    pub executed: Executed,
+}
+
+/// where [`World::start_tracing`] sends its records, in the order the instructions
+/// ran. In a parallel step that is the update order, like in a sequential one.
+pub trait TraceSink: Send {
+    fn record(&mut self, record: &TraceRecord);
+    /// called by [`World::stop_tracing`], sinks that write somewhere flush here and
+    /// report the first error they ran into.
+    fn finish(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// keeps the last `capacity` records in memory. Clones share the buffer, keep one to
+/// read what the world traced into the other.
+#[derive(Clone, Debug)]
+pub struct RingBuffer {
+    capacity: usize,
+    records: Arc<Mutex<VecDeque<TraceRecord>>>,
+}
+impl RingBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, records: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))) }
+    }
+    /// the records, oldest first.
+    pub fn records(&self) -> Vec<TraceRecord> {
+        self.records.lock().unwrap().iter().copied().collect()
+    }
+    pub fn clear(&self) {
+        self.records.lock().unwrap().clear();
+    }
+}
+impl TraceSink for RingBuffer {
+    fn record(&mut self, record: &TraceRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut records = self.records.lock().unwrap();
+        if records.len() == self.capacity {
+            records.pop_front();
+        }
+        records.push_back(*record);
+    }
+}
+
+/// a writer that remembers its first error instead of failing every record.
+struct Output<W: Write> {
+    writer: W,
+    error: Option<std::io::Error>,
+}
+impl<W: Write> Output<W> {
+    fn write(&mut self, write: impl FnOnce(&mut W) -> std::io::Result<()>) {
+        if self.error.is_none() {
+            self.error = write(&mut self.writer).err();
+        }
+    }
+    fn finish(&mut self) -> std::io::Result<()> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => self.writer.flush(),
+        }
+    }
+}
+
+/// one JSON object per record and line, e.g.
+/// `{"step":3,"entity":{"index":0,"generation":1},"rip":2,"word":3472626271,
+/// "instruction":"mul r0.b3, r1.b3","taken":true,"registers":{"r0":13147...},
+/// "from":[22,109],"to":[22,109],"energy":508}`. `registers` holds the 64 bit
+/// registers the instruction changed.
+pub struct JsonLines<W: Write> {
+    output: Output<W>,
+}
+impl<W: Write> JsonLines<W> {
+    pub fn new(writer: W) -> Self {
+        Self { output: Output { writer, error: None } }
+    }
+}
+#[derive(Serialize)]
+struct JsonRecord {
+    step: u64,
+    entity: EntityHandle,
+    rip: usize,
+    word: u32,
+    instruction: String,
+    taken: bool,
+    registers: BTreeMap<String, u64>,
+    from: [u32; 2],
+    to: [u32; 2],
+    energy: u32,
+}
+impl<W: Write + Send> TraceSink for JsonLines<W> {
+    fn record(&mut self, record: &TraceRecord) {
+        let executed = &record.executed;
+        let json = JsonRecord {
+            step: record.step,
+            entity: record.entity,
+            rip: executed.index,
+            word: executed.word,
+            instruction: format!("{} {}", executed.event, executed.response).trim_start().to_string(),
+            taken: executed.taken,
+            registers: executed.changed_registers()
+                .filter(|register| register.is_long())
+                .map(|register| (register.to_string(), executed.after.get(register)))
+                .collect(),
+            from: executed.before.pos(),
+            to: executed.after.pos(),
+            energy: executed.after.get_energy(),
+        };
+        self.output.write(|writer| {
+            serde_json::to_writer(&mut *writer, &json)?;
+            writer.write_all(b"\n")
+        });
+    }
+    fn finish(&mut self) -> std::io::Result<()> {
+        self.output.finish()
+    }
+}
+
+/// first bytes of a [`Compact`] trace.
+const MAGIC: [u8; 4] = *b"GPCT";
+/// bumped whenever the layout of [`CompactRecord`] changes.
+pub const TRACE_VERSION: u32 = 1;
+
+/// fixed size little endian records after an 8 byte header of `GPCT` and
+/// [`TRACE_VERSION`], see [`CompactRecord`] for the layout. Read them back with
+/// [`CompactRecord::read_all`].
+pub struct Compact<W: Write> {
+    output: Output<W>,
+}
+impl<W: Write> Compact<W> {
+    pub fn new(writer: W) -> Self {
+        let mut output = Output { writer, error: None };
+        output.write(|writer| {
+            writer.write_all(&MAGIC)?;
+            writer.write_all(&TRACE_VERSION.to_le_bytes())
+        });
+        Self { output }
+    }
+}
+impl<W: Write + Send> TraceSink for Compact<W> {
+    fn record(&mut self, record: &TraceRecord) {
+        let bytes = CompactRecord::from(record).to_bytes();
+        self.output.write(|writer| writer.write_all(&bytes));
+    }
+    fn finish(&mut self) -> std::io::Result<()> {
+        self.output.finish()
+    }
+}
+
+/// a record of a [`Compact`] trace. The registers before an instruction are the ones
+/// after the previous record of the same entity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompactRecord {
+    pub step: u64,
+    pub entity: EntityHandle,
+    pub rip: u32,
+    /// the code word, decode it with [`crate::new2::entity::EventResponse::from_word`].
+    pub word: u32,
+    pub taken: bool,
+    /// both 64 bit registers after the instruction.
+    pub registers: [u64; 2],
+    pub from: [u32; 2],
+    pub to: [u32; 2],
+    pub energy: u32,
+}
+impl From<&TraceRecord> for CompactRecord {
+    fn from(record: &TraceRecord) -> Self {
+        let executed = &record.executed;
+        Self {
+            step: record.step,
+            entity: record.entity,
+            rip: executed.index as u32,
+            word: executed.word,
+            taken: executed.taken,
+            registers: [executed.after.get(Register::LongRegister0), executed.after.get(Register::LongRegister1)],
+            from: executed.before.pos(),
+            to: executed.after.pos(),
+            energy: executed.after.get_energy(),
+        }
+    }
+}
+impl CompactRecord {
+    pub const SIZE: usize = 61;
+    /// `step`, `entity.index`, `entity.generation`, `rip`, `word`, `taken` as one byte,
+    /// `registers`, `from`, `to` and `energy` in that order.
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        let fields = [
+            &self.step.to_le_bytes()[..], &self.entity.index.to_le_bytes(), &self.entity.generation.to_le_bytes(),
+            &self.rip.to_le_bytes(), &self.word.to_le_bytes(), &[self.taken as u8],
+            &self.registers[0].to_le_bytes(), &self.registers[1].to_le_bytes(),
+            &self.from[0].to_le_bytes(), &self.from[1].to_le_bytes(),
+            &self.to[0].to_le_bytes(), &self.to[1].to_le_bytes(), &self.energy.to_le_bytes(),
+        ];
+        let mut offset = 0;
+        for field in fields {
+            bytes[offset..offset+field.len()].copy_from_slice(field);
+            offset += field.len();
+        }
+        bytes
+    }
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Self {
+        let mut bytes = &bytes[..];
+        let step = u64::from_le_bytes(take(&mut bytes));
+        let mut u32 = || u32::from_le_bytes(take(&mut bytes));
+        let (index, generation, rip, word) = (u32(), u32(), u32(), u32());
+        let taken = take::<1>(&mut bytes)[0] != 0;
+        let registers = [u64::from_le_bytes(take(&mut bytes)), u64::from_le_bytes(take(&mut bytes))];
+        let mut u32 = || u32::from_le_bytes(take(&mut bytes));
+        let (from, to, energy) = ([u32(), u32()], [u32(), u32()], u32());
+        Self { step, entity: EntityHandle { index, generation }, rip, word, taken, registers, from, to, energy }
+    }
+    /// reads a whole [`Compact`] trace.
+    pub fn read_all(mut reader: impl Read) -> std::io::Result<Vec<Self>> {
+        let invalid = |reason: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, reason.to_string());
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        if header[..4] != MAGIC {
+            return Err(invalid("not a compact trace"));
+        }
+        if u32::from_le_bytes(header[4..].try_into().unwrap()) != TRACE_VERSION {
+            return Err(invalid("unsupported compact trace version"));
+        }
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+        if data.len()%Self::SIZE != 0 {
+            return Err(invalid("the trace ends in the middle of a record"));
+        }
+        Ok(data.chunks_exact(Self::SIZE).map(|chunk| Self::from_bytes(chunk.try_into().unwrap())).collect())
+    }
+}
+
+/// the first `N` bytes, `bytes` is left with the rest.
+fn take<const N: usize>(bytes: &mut &[u8]) -> [u8; N] {
+    let (head, rest) = bytes.split_at(N);
+    *bytes = rest;
+    head.try_into().unwrap()
+}
+
+impl World {
+    /// sends every instruction entities run from the next [`World::step`] on to `sink`
+    /// until [`World::stop_tracing`]. Instructions run through
+    /// [`World::step_instruction`] are not traced.
+    pub fn start_tracing(&mut self, sink: impl TraceSink + 'static) {
+        self.tracer = Some(Mutex::new(Box::new(sink)));
+    }
+    /// returns what [`TraceSink::finish`] returned, `None` if the world was not tracing.
+    pub fn stop_tracing(&mut self) -> Option<std::io::Result<()>> {
+        let tracer = self.tracer.take()?;
+        Some(tracer.into_inner().unwrap().finish())
+    }
+    pub fn is_tracing(&self) -> bool {
+        self.tracer.is_some()
+    }
+    /// sends the instructions the entity at `idx` ran in its turn to the tracer. The
+    /// last one is updated with the effect the world carried out since.
+    pub(crate) fn trace(&mut self, idx: usize, mut executed: Vec<Executed>) {
+        let Some(tracer) = self.tracer.as_mut() else {
+            return;
+        };
+        if let Some(last) = executed.last_mut() {
+            last.after = *self.entities[idx].inner();
+        }
+        let tracer = tracer.get_mut().unwrap();
+        for executed in executed {
+            tracer.record(&TraceRecord { step: self.steps, entity: self.entities[idx].handle(), executed });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new2::entity::{asm::assemble, GPCAEntity};
+
+    /// the records of an entity at 1,1 adding r1 to r0 and moving right, one per step.
+    fn records(count: usize) -> Vec<TraceRecord> {
+        let code = assemble("add r0, r1\nmove 0").unwrap();
+        let mut world = World::new(vec![], 1, 5, 5, true, 0.0, Some(1));
+        let entity = world.push_entity(GPCAEntity::new(1, 1, 0, 1, 100, 0, code));
+        (0..count as u64).map(|step| TraceRecord { step, entity, executed: world.step_instruction(entity).unwrap() }).collect()
+    }
+
+    #[test]
+    fn ring_buffers_drop_the_oldest_records() {
+        let mut buffer = RingBuffer::new(3);
+        for record in records(5) {
+            buffer.record(&record);
+        }
+        assert_eq!(buffer.records().iter().map(|record| record.step).collect::<Vec<_>>(), [2, 3, 4]);
+        buffer.clear();
+        assert!(buffer.records().is_empty());
+        let mut empty = RingBuffer::new(0);
+        empty.record(&records(1)[0]);
+        assert!(empty.records().is_empty());
+    }
+
+    #[test]
+    fn ring_buffers_share_what_the_world_traced() {
+        let code = assemble("add r0, r1").unwrap();
+        let mut world = World::new(vec![], 1, 5, 5, true, 0.0, Some(1));
+        world.push_entity(GPCAEntity::new(1, 1, 0, 1, 100, 0, code));
+        let buffer = RingBuffer::new(2);
+        world.start_tracing(buffer.clone());
+        for _ in 0..5 {
+            world.step(|_| {}, |_| {});
+        }
+        assert!(world.stop_tracing().unwrap().is_ok());
+        let records = buffer.records();
+        assert_eq!(records.iter().map(|record| record.step).collect::<Vec<_>>(), [3, 4]);
+        assert_eq!(records[1].executed.after.get(Register::LongRegister0), 5);
+    }
+
+    #[test]
+    fn json_lines_hold_the_executed_instruction() {
+        let records = records(2);
+        let mut bytes = vec![];
+        let mut sink = JsonLines::new(&mut bytes);
+        for record in &records {
+            sink.record(record);
+        }
+        sink.finish().unwrap();
+        let lines = String::from_utf8(bytes).unwrap().lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        let entity = serde_json::json!({ "index": records[0].entity.index, "generation": records[0].entity.generation });
+        assert_eq!(lines, [
+            serde_json::json!({
+                "step": 0, "entity": entity, "rip": 0, "word": records[0].executed.word, "instruction": "add r0, r1",
+                "taken": true, "registers": { "r0": 1 }, "from": [1, 1], "to": [1, 1], "energy": 100,
+            }),
+            serde_json::json!({
+                "step": 1, "entity": entity, "rip": 1, "word": records[1].executed.word, "instruction": "move 0",
+                "taken": true, "registers": {}, "from": [1, 1], "to": [2, 1], "energy": 100,
+            }),
+        ]);
+    }
+
+    #[test]
+    fn compact_records_read_back() {
+        let records = records(2);
+        let mut bytes = vec![];
+        let mut sink = Compact::new(&mut bytes);
+        for record in &records {
+            sink.record(record);
+        }
+        sink.finish().unwrap();
+        assert_eq!(bytes.len(), 8+2*CompactRecord::SIZE);
+        let read = CompactRecord::read_all(&bytes[..]).unwrap();
+        assert_eq!(read, records.iter().map(CompactRecord::from).collect::<Vec<_>>());
+        assert_eq!(read[1], CompactRecord {
+            step: 1, entity: records[1].entity, rip: 1, word: records[1].executed.word, taken: true,
+            registers: [1, 1], from: [1, 1], to: [2, 1], energy: 100,
+        });
+        assert!(CompactRecord::read_all(&bytes[..bytes.len()-1]).is_err());
+        assert!(CompactRecord::read_all(&b"GPCA\x01\0\0\0"[..]).is_err());
+    }
+}
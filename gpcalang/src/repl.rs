@@ -0,0 +1,215 @@
+use std::io::{BufRead, Write};
+
+use gpcalang::{entity::bytecode::{disassemble, Register, ResponseKind}, world::{actions, config::WorldConfig}, Breakpoint, Debugger, EntityHandle, Stop, World};
+
+const HELP: &str = "step [N]              run the next N instructions of the entity
+continue [N]          run until a breakpoint, at most N instructions
+tick [N]              step the whole world N times
+break INDEX|KIND      break before an instruction index or a response kind
+                      (nop, arithmetic, jump, move, call, harvest, signal)
+delete N              remove breakpoint N
+breakpoints           list the breakpoints
+watch REG             print REG after every instruction, e.g. r0 or r1.b2
+unwatch REG
+attach HANDLE|X Y     follow another entity
+info                  registers, position and energy of the entity
+list                  the code of the entity
+help
+quit";
+
+/// how many instructions `continue` runs without a limit.
+const CONTINUE_LIMIT: usize = 1_000_000;
+
+fn count(arg: Option<&str>, default: usize) -> Result<usize, String> {
+    arg.map_or(Ok(default), |arg| arg.parse().map_err(|err| format!("invalid count '{arg}': {err}")))
+}
+
+fn info(world: &World, debugger: &Debugger) -> Result<(), String> {
+    let entity = world.entity(debugger.entity()).ok_or("the entity is gone")?;
+    let inner = entity.inner();
+    println!("entity {} at {},{} with {} energy, next instruction {}", entity.handle(), entity.x(), entity.y(), entity.get_energy(), entity.next_index());
+    for (long, bytes) in [(Register::LongRegister0, &Register::ALL[2..6]), (Register::LongRegister1, &Register::ALL[6..10])] {
+        let bytes = bytes.iter().map(|register| format!("{register} = {:#04x}", inner.get(*register))).collect::<Vec<_>>();
+        println!("  {long} = {:#018x}  {}", inner.get(long), bytes.join("  "));
+    }
+    Ok(())
+}
+
+fn watched(world: &World, debugger: &Debugger) {
+    if let Some(watched) = debugger.watched(world) {
+        for (register, value) in watched {
+            println!("        {register} = {value:#x}");
+        }
+    }
+}
+
+fn command(world: &mut World, debugger: &mut Debugger, line: &str) -> Result<bool, String> {
+    let mut args = line.split_whitespace();
+    let Some(command) = args.next() else {
+        return Ok(true);
+    };
+    match command {
+        "step" | "s" => {
+            for _ in 0..count(args.next(), 1)? {
+                let executed = debugger.step(world).ok_or("the entity is gone")?;
+                println!("{executed}");
+                watched(world, debugger);
+            }
+        }
+        "continue" | "c" => {
+            match debugger.resume(world, count(args.next(), CONTINUE_LIMIT)?) {
+                Stop::Breakpoint(idx) => println!("stopped at breakpoint {idx}, {}", debugger.breakpoints()[idx]),
+                Stop::Gone => println!("the entity is gone"),
+                Stop::Limit => println!("no breakpoint was hit"),
+            }
+            watched(world, debugger);
+        }
+        "tick" | "t" => {
+            for _ in 0..count(args.next(), 1)? {
+                world.step(|_| {}, |_| {});
+            }
+            if !world.is_alive(debugger.entity()) {
+                println!("the entity is gone");
+            }
+        }
+        "break" | "b" => {
+            let at = args.next().ok_or("break needs an instruction index or a response kind")?;
+            let breakpoint = match at.parse::<usize>() {
+                Ok(index) => Breakpoint::Instruction(index),
+                Err(_) => Breakpoint::Response(at.parse::<ResponseKind>()?),
+            };
+            debugger.add_breakpoint(breakpoint);
+        }
+        "delete" | "d" => {
+            let idx = count(args.next(), 0)?;
+            debugger.remove_breakpoint(idx).ok_or(format!("there is no breakpoint {idx}"))?;
+        }
+        "breakpoints" => {
+            for (idx, breakpoint) in debugger.breakpoints().iter().enumerate() {
+                println!("{idx}: {breakpoint}");
+            }
+        }
+        "watch" | "w" => debugger.watch(args.next().ok_or("watch needs a register")?.parse()?),
+        "unwatch" => debugger.unwatch(args.next().ok_or("unwatch needs a register")?.parse()?),
+        "attach" | "a" => {
+            let args = args.collect::<Vec<_>>();
+            let handle = match args[..] {
+                [handle] => handle.parse::<EntityHandle>()?,
+                [x, y] => {
+                    let pos = [x.parse().map_err(|err| format!("invalid x '{x}': {err}"))?, y.parse().map_err(|err| format!("invalid y '{y}': {err}"))?];
+                    world.entity_at(pos).ok_or(format!("there is no entity at {x},{y}"))?.handle()
+                }
+                _ => return Err("attach needs a handle or a position".to_string()),
+            };
+            world.entity(handle).ok_or(format!("there is no entity {handle}"))?;
+            debugger.attach(handle);
+            info(world, debugger)?;
+        }
+        "info" | "i" => info(world, debugger)?,
+        "list" | "l" => {
+            let entity = world.entity(debugger.entity()).ok_or("the entity is gone")?;
+            for word in disassemble(entity.code()).words {
+                let next = if word.index == entity.next_index() { "->" } else { "  " };
+                println!("{next} {word}");
+            }
+        }
+        "help" | "h" => println!("{HELP}"),
+        "quit" | "q" => return Ok(false),
+        _ => return Err(format!("unknown command '{command}', try help")),
+    }
+    Ok(true)
+}
+
+/// reads debugger commands from stdin until `quit` or the end of the input.
+pub fn run(config: WorldConfig, entity: EntityHandle) -> Result<(), String> {
+    let mut world = config.build(actions::standard).map_err(|err| err.to_string())?;
+    world.entity(entity).ok_or(format!("there is no entity {entity}"))?;
+    let mut debugger = Debugger::new(entity);
+    info(&world, &debugger)?;
+    let mut lines = std::io::stdin().lock().lines();
+    loop {
+        print!("(gpca) ");
+        std::io::stdout().flush().map_err(|err| err.to_string())?;
+        let Some(line) = lines.next() else {
+            return Ok(());
+        };
+        let line = line.map_err(|err| err.to_string())?;
+        match command(&mut world, &mut debugger, &line) {
+            Ok(true) => {}
+            Ok(false) => return Ok(()),
+            Err(err) => println!("{err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gpcalang::{entity::asm::assemble, GPCAEntity};
+
+    use super::*;
+
+    /// a debugger on an entity at 1,1 counting r0 up by r1 before it moves right.
+    fn counting() -> (World, Debugger) {
+        let code = assemble("start:\n    add r0, r1\n    add r0, r1\n    move 0\n    jmp start").unwrap();
+        let mut world = World::new(vec![], 2, 5, 5, true, 0.0, Some(1));
+        let entity = world.push_entity(GPCAEntity::new(1, 1, 0, 1, 100, 0, code));
+        (world, Debugger::new(entity))
+    }
+
+    fn r0(world: &World, debugger: &Debugger) -> u64 {
+        world.entity(debugger.entity()).unwrap().inner().get(Register::LongRegister0)
+    }
+
+    #[test]
+    fn step_runs_single_instructions() {
+        let (mut world, mut debugger) = counting();
+        assert_eq!(command(&mut world, &mut debugger, "step"), Ok(true));
+        assert_eq!(r0(&world, &debugger), 1);
+        assert_eq!(command(&mut world, &mut debugger, "s 2"), Ok(true));
+        assert_eq!(r0(&world, &debugger), 2);
+        assert_eq!(world.entity(debugger.entity()).unwrap().pos(), [2, 1]);
+        assert!(command(&mut world, &mut debugger, "step x").is_err());
+    }
+
+    #[test]
+    fn continue_stops_at_breakpoints() {
+        let (mut world, mut debugger) = counting();
+        assert_eq!(command(&mut world, &mut debugger, "break 2"), Ok(true));
+        assert_eq!(command(&mut world, &mut debugger, "b move"), Ok(true));
+        assert_eq!(debugger.breakpoints(), [Breakpoint::Instruction(2), Breakpoint::Response(ResponseKind::Move)]);
+        assert_eq!(command(&mut world, &mut debugger, "continue"), Ok(true));
+        assert_eq!(world.entity(debugger.entity()).unwrap().next_index(), 2);
+        assert_eq!(r0(&world, &debugger), 2);
+        assert_eq!(command(&mut world, &mut debugger, "delete 0"), Ok(true));
+        assert_eq!(debugger.breakpoints(), [Breakpoint::Response(ResponseKind::Move)]);
+        assert!(command(&mut world, &mut debugger, "delete 1").is_err());
+        assert!(command(&mut world, &mut debugger, "break teleport").is_err());
+        assert_eq!(command(&mut world, &mut debugger, "c 3"), Ok(true));
+        assert_eq!(world.entity(debugger.entity()).unwrap().next_index(), 1);
+    }
+
+    #[test]
+    fn watch_and_attach_pick_what_is_inspected() {
+        let (mut world, mut debugger) = counting();
+        let other = world.push_entity(GPCAEntity::new(3, 3, 7, 0, 100, 0, vec![0]));
+        assert_eq!(command(&mut world, &mut debugger, "watch r1.b0"), Ok(true));
+        assert!(command(&mut world, &mut debugger, "watch r2").is_err());
+        assert_eq!(command(&mut world, &mut debugger, "attach 3 3"), Ok(true));
+        assert_eq!(debugger.entity(), other);
+        assert_eq!(debugger.watched(&world), Some(vec![(Register::ByteRegister1_0, 0)]));
+        assert_eq!(r0(&world, &debugger), 7);
+        assert!(command(&mut world, &mut debugger, "attach 4 4").is_err());
+        assert_eq!(debugger.entity(), other);
+        assert_eq!(command(&mut world, &mut debugger, "info"), Ok(true));
+    }
+
+    #[test]
+    fn quit_ends_and_unknown_commands_fail() {
+        let (mut world, mut debugger) = counting();
+        assert_eq!(command(&mut world, &mut debugger, ""), Ok(true));
+        assert_eq!(command(&mut world, &mut debugger, "tick 2"), Ok(true));
+        assert_eq!(world.steps(), 2);
+        assert!(command(&mut world, &mut debugger, "jump").is_err());
+        assert_eq!(command(&mut world, &mut debugger, "q"), Ok(false));
+    }
+}